@@ -0,0 +1,111 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::rng;
+
+pin_project! {
+    /// Flip random bits in the data read, to test checksums and other verification.
+    ///
+    /// Each bit is flipped with probability `rate`. The bits chosen depend only on the
+    /// `seed` and the position in the stream, not on how the stream is split into reads,
+    /// so runs are reproducible.
+    pub struct BitRot<R> {
+        #[pin]
+        inner: R,
+        rate: f64,
+        state: u64,
+        flipped: u64,
+    }
+}
+
+impl<R> BitRot<R> {
+    /// # Panics
+    ///
+    /// Panics if `rate` is not between `0.0` and `1.0`.
+    pub fn new(inner: R, rate: f64, seed: u64) -> Self {
+        assert!((0.0..=1.0).contains(&rate), "rate must be a probability");
+        BitRot {
+            inner,
+            rate,
+            state: seed,
+            flipped: 0,
+        }
+    }
+
+    /// The number of bits which have been flipped so far.
+    pub fn flipped(&self) -> u64 {
+        self.flipped
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for BitRot<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        for byte in &mut buf[..found] {
+            for bit in 0..8 {
+                if rng::unit(this.state) < *this.rate {
+                    *byte ^= 1 << bit;
+                    *this.flipped += 1;
+                }
+            }
+        }
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::BitRot;
+    use crate::ShortRead;
+
+    async fn rot(rate: f64, sizes: Vec<usize>) -> (Vec<u8>, u64) {
+        let mut r = BitRot::new(
+            ShortRead::new(io::Cursor::new([0u8; 16]), sizes.into_iter()),
+            rate,
+            1234,
+        );
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).await.unwrap();
+        (out, r.flipped())
+    }
+
+    #[test]
+    fn seeded() {
+        task::block_on(async {
+            let (out, flipped) = rot(0.05, vec![16]).await;
+            assert_eq!(vec![0, 0, 64, 0, 0, 2, 0, 19, 0, 0, 0, 0, 0, 0, 0, 0], out);
+            assert_eq!(5, flipped);
+
+            // the same bits are flipped, however the reads are split
+
+            assert_eq!((out, flipped), rot(0.05, vec![3, 0, 5, 1, 7]).await);
+        });
+    }
+
+    #[test]
+    fn extremes() {
+        task::block_on(async {
+            assert_eq!((vec![0u8; 16], 0), rot(0.0, vec![16]).await);
+            assert_eq!((vec![0xffu8; 16], 128), rot(1.0, vec![16]).await);
+        });
+    }
+}
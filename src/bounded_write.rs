@@ -0,0 +1,131 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::task::Waker;
+use futures::AsyncWrite;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Limit how much may be written before a flush, to apply backpressure on top of
+    /// a buffering writer.
+    ///
+    /// Once `bound` bytes have been written since the last flush, writes return
+    /// `Poll::Pending` until a flush completes. Writes are shortened so the bound is
+    /// never exceeded.
+    pub struct BoundedWrite<W> {
+        #[pin]
+        inner: W,
+        bound: usize,
+        unflushed: usize,
+        waker: Option<Waker>,
+    }
+}
+
+impl<W> BoundedWrite<W> {
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero.
+    pub fn new(inner: W, bound: usize) -> Self {
+        assert!(bound > 0, "bound must be non-zero");
+        BoundedWrite {
+            inner,
+            bound,
+            unflushed: 0,
+            waker: None,
+        }
+    }
+
+    /// How many bytes have been written since the last flush.
+    pub fn unflushed(&self) -> usize {
+        self.unflushed
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite> BoundedWrite<W> {
+    fn flushed(self: Pin<&mut Self>) {
+        let this = self.project();
+        *this.unflushed = 0;
+        if let Some(waker) = this.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for BoundedWrite<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let space = *this.bound - *this.unflushed;
+        if 0 == space && !buf.is_empty() {
+            *this.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let written = ready!(this.inner.poll_write(cx, &buf[..buf.len().min(space)]))?;
+        *this.unflushed += written;
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().project().inner.poll_flush(cx))?;
+        self.flushed();
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().project().inner.poll_close(cx))?;
+        self.flushed();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+
+    use futures::task::noop_waker_ref;
+    use futures::task::Context;
+    use futures::task::Poll;
+    use futures::AsyncWrite;
+
+    use crate::BoundedWrite;
+
+    #[test]
+    fn backpressure() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut w = BoundedWrite::new(Vec::new(), 5);
+        match Pin::new(&mut w).poll_write(&mut cx, b"abc") {
+            Poll::Ready(Ok(3)) => (),
+            other => panic!("unexpected: {:?}", other),
+        }
+        match Pin::new(&mut w).poll_write(&mut cx, b"defg") {
+            Poll::Ready(Ok(2)) => (),
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert_eq!(5, w.unflushed());
+        assert!(Pin::new(&mut w).poll_write(&mut cx, b"fg").is_pending());
+
+        match Pin::new(&mut w).poll_flush(&mut cx) {
+            Poll::Ready(Ok(())) => (),
+            other => panic!("unexpected: {:?}", other),
+        }
+        match Pin::new(&mut w).poll_write(&mut cx, b"fg") {
+            Poll::Ready(Ok(2)) => (),
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert_eq!(b"abcdefg", w.get_ref().as_slice());
+    }
+}
@@ -0,0 +1,113 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Deliver data in bursts, separated by idle periods, like a bursty network.
+    ///
+    /// Each `(burst_bytes, idle_polls)` from the `pattern` iterator allows up to
+    /// `burst_bytes` to be read, in as many reads as the caller likes, followed by
+    /// `idle_polls` polls which return `Poll::Pending`, with an immediate wakeup.
+    /// When the iterator runs out, reads return EOF, as with `ShortRead`.
+    pub struct BurstRead<R, I> {
+        #[pin]
+        inner: R,
+        pattern: I,
+        burst: usize,
+        idle: usize,
+    }
+}
+
+impl<R, I: Iterator<Item = (usize, usize)>> BurstRead<R, I> {
+    pub fn new(inner: R, pattern: I) -> Self {
+        BurstRead {
+            inner,
+            pattern,
+            burst: 0,
+            idle: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead, I: Iterator<Item = (usize, usize)>> AsyncRead for BurstRead<R, I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        while 0 == *this.burst {
+            if *this.idle > 0 {
+                *this.idle -= 1;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            match this.pattern.next() {
+                Some((burst, idle)) => {
+                    *this.burst = burst;
+                    *this.idle = idle;
+                }
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+
+        let wanted = buf.len().min(*this.burst);
+        let result = this.inner.poll_read(cx, &mut buf[..wanted]);
+        if let Poll::Ready(Ok(n)) = result {
+            *this.burst -= n;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+
+    use futures::io;
+    use futures::task::noop_waker_ref;
+    use futures::task::Context;
+    use futures::task::Poll;
+    use futures::AsyncRead;
+
+    use crate::BurstRead;
+
+    #[test]
+    fn alternates() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut r = BurstRead::new(
+            io::Cursor::new(b"abcdefghij".to_vec()),
+            vec![(5, 2), (3, 1), (9, 0)].into_iter(),
+        );
+        let mut buf = [0u8; 4];
+        let mut seen = Vec::new();
+        loop {
+            match Pin::new(&mut r).poll_read(&mut cx, &mut buf) {
+                Poll::Pending => seen.push(None),
+                Poll::Ready(Ok(0)) => break,
+                Poll::Ready(Ok(n)) => seen.push(Some(buf[..n].to_vec())),
+                Poll::Ready(Err(e)) => panic!("{:?}", e),
+            }
+        }
+        assert_eq!(
+            vec![
+                Some(b"abcd".to_vec()),
+                Some(b"e".to_vec()),
+                None,
+                None,
+                Some(b"fgh".to_vec()),
+                None,
+                Some(b"ij".to_vec()),
+            ],
+            seen
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::rng;
+use crate::ReadEvent;
+
+pin_project! {
+    /// Randomly, but reproducibly for the `seed`, shorten reads, inject `Pending`s, with
+    /// an immediate wakeup, and fail reads with `Interrupted`, to stress-test parsers.
+    ///
+    /// This is `ShortRead` and `ErrorRead` in one, without having to pick the schedule.
+    /// Everything the caller saw is recorded in the `trace`, which can be replayed with
+    /// `Scripted`.
+    pub struct Chaos<R> {
+        #[pin]
+        inner: R,
+        state: u64,
+        pending_rate: f64,
+        error_rate: f64,
+        trace: Vec<ReadEvent>,
+    }
+}
+
+impl<R> Chaos<R> {
+    /// Wrap a reader, injecting `Pending`s into a quarter of reads, and errors into one in twenty.
+    pub fn new(inner: R, seed: u64) -> Self {
+        Chaos {
+            inner,
+            state: seed,
+            pending_rate: 0.25,
+            error_rate: 0.05,
+            trace: Vec::new(),
+        }
+    }
+
+    /// The probability of injecting a `Pending` into each read.
+    pub fn set_pending_rate(&mut self, rate: f64) {
+        self.pending_rate = rate;
+    }
+
+    /// The probability of failing each read with `Interrupted`.
+    pub fn set_error_rate(&mut self, rate: f64) {
+        self.error_rate = rate;
+    }
+
+    /// Every result returned so far.
+    pub fn trace(&self) -> &[ReadEvent] {
+        &self.trace
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Chaos<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if buf.is_empty() {
+            return this.inner.poll_read(cx, buf);
+        }
+
+        let dice = rng::unit(this.state);
+        if dice < *this.pending_rate {
+            this.trace.push(ReadEvent::Pending);
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        if dice < *this.pending_rate + *this.error_rate {
+            this.trace
+                .push(ReadEvent::Error(io::ErrorKind::Interrupted));
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "injected by chaos",
+            )));
+        }
+
+        let wanted = 1 + (rng::next(this.state) % buf.len() as u64) as usize;
+        let result = match this.inner.poll_read(cx, &mut buf[..wanted]) {
+            Poll::Pending => {
+                this.trace.push(ReadEvent::Pending);
+                return Poll::Pending;
+            }
+            Poll::Ready(result) => result,
+        };
+        this.trace.push(match &result {
+            Ok(0) => ReadEvent::Eof,
+            Ok(found) => ReadEvent::Data(buf[..*found].to_vec()),
+            Err(e) => ReadEvent::Error(e.kind()),
+        });
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncRead;
+
+    use crate::Chaos;
+    use crate::DequeReader;
+    use crate::ReadEvent;
+    use crate::Scripted;
+
+    /// Every token, retrying reads which are interrupted.
+    async fn tokens(r: impl AsyncRead + Unpin) -> Vec<Vec<u8>> {
+        let mut m = DequeReader::new(r);
+        m.set_bypass(false);
+        let mut found = Vec::new();
+        loop {
+            match m.next_token(|b| b' ' == b).await {
+                Ok(Some(token)) => found.push(token),
+                Ok(None) => return found,
+                Err(e) if io::ErrorKind::Interrupted == e.kind() => continue,
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn tolerated() {
+        task::block_on(async {
+            let input = b"lorem ipsum dolor sit amet consectetur adipiscing elit";
+            let expected = tokens(io::Cursor::new(input)).await;
+
+            let mut chaos = Chaos::new(io::Cursor::new(input), 9);
+            let found = tokens(&mut chaos).await;
+            assert_eq!(expected, found);
+
+            let trace = chaos.trace().to_vec();
+            assert!(trace.contains(&ReadEvent::Error(io::ErrorKind::Interrupted)));
+            assert!(trace.contains(&ReadEvent::Pending));
+
+            // the trace reproduces the run
+            assert_eq!(expected, tokens(Scripted::new(trace)).await);
+        });
+    }
+}
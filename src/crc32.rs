@@ -0,0 +1,25 @@
+/// The CRC-32 (IEEE 802.3) of some data, as used by `DequeReader::read_checked_frame`.
+///
+/// This is a simple bitwise implementation; it is not fast.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crc32;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(0, crc32(b""));
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+    }
+}
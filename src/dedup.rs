@@ -0,0 +1,92 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Suppress reads which return exactly the same bytes as the previous read,
+    /// reading again instead, to test retransmission handling.
+    ///
+    /// Note that this compares whole reads, so is sensitive to how the inner reader,
+    /// and the caller's buffer size, split the data.
+    pub struct Dedup<R> {
+        #[pin]
+        inner: R,
+        last: Vec<u8>,
+        suppressed: usize,
+    }
+}
+
+impl<R> Dedup<R> {
+    pub fn new(inner: R) -> Self {
+        Dedup {
+            inner,
+            last: Vec::new(),
+            suppressed: 0,
+        }
+    }
+
+    /// The number of reads which have been suppressed.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Dedup<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut this = self.project();
+        loop {
+            let found = ready!(this.inner.as_mut().poll_read(cx, buf))?;
+            if 0 != found && buf[..found] == this.last[..] {
+                *this.suppressed += 1;
+                continue;
+            }
+            this.last.clear();
+            this.last.extend_from_slice(&buf[..found]);
+            return Poll::Ready(Ok(found));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::Dedup;
+    use crate::ShortRead;
+
+    #[test]
+    fn repeated() {
+        task::block_on(async {
+            let mut r = Dedup::new(ShortRead::new(
+                io::Cursor::new(b"aaaabbaa"),
+                vec![2, 2, 2, 2].into_iter(),
+            ));
+            let mut chunks = Vec::new();
+            let mut buf = [0u8; 16];
+            loop {
+                let n = r.read(&mut buf).await.unwrap();
+                if 0 == n {
+                    break;
+                }
+                chunks.push(buf[..n].to_vec());
+            }
+            assert_eq!(vec![b"aa".to_vec(), b"bb".to_vec(), b"aa".to_vec()], chunks);
+            assert_eq!(1, r.suppressed());
+        });
+    }
+}
@@ -0,0 +1,157 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::timer::Deadline;
+use crate::Timer;
+
+pin_project! {
+    /// Delay the first read by a fixed duration, then pass reads through unchanged,
+    /// to simulate connection warm-up, such as a handshake.
+    ///
+    /// The delay starts when the reader is first polled.
+    pub struct DelayFirst<R> {
+        #[pin]
+        inner: R,
+        delay: Option<Duration>,
+        deadline: Option<Deadline>,
+        timer: Option<Arc<dyn Timer>>,
+    }
+}
+
+impl<R> DelayFirst<R> {
+    pub fn new(inner: R, delay: Duration) -> Self {
+        DelayFirst {
+            inner,
+            delay: Some(delay),
+            deadline: None,
+            timer: None,
+        }
+    }
+
+    /// Use this `Timer` for the delay, instead of the default `ThreadTimer`.
+    pub fn set_timer(&mut self, timer: Arc<dyn Timer>) {
+        self.timer = Some(timer);
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for DelayFirst<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if let Some(delay) = *this.delay {
+            let timer = this.timer.as_ref();
+            let deadline = this
+                .deadline
+                .get_or_insert_with(|| Deadline::after(timer, delay));
+            ready!(deadline.poll(cx));
+            *this.delay = None;
+            *this.deadline = None;
+        }
+        this.inner.poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use async_std::task;
+    use futures::future;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+    use futures::task::noop_waker_ref;
+    use futures::task::Context;
+    use futures::task::Poll;
+    use futures::task::Waker;
+    use futures::AsyncRead as _;
+
+    use crate::DelayFirst;
+    use crate::Sleep;
+    use crate::Timer;
+
+    #[test]
+    fn only_first() {
+        task::block_on(async {
+            let delay = Duration::from_millis(50);
+            let mut r = DelayFirst::new(io::Cursor::new(b"ab"), delay);
+            let mut buf = [0u8; 1];
+
+            let start = Instant::now();
+            assert_eq!(1, r.read(&mut buf).await.unwrap());
+            assert!(start.elapsed() >= delay);
+
+            // later reads are ready immediately, without waiting
+            let mut cx = Context::from_waker(noop_waker_ref());
+            for expected in &[1, 0] {
+                match Pin::new(&mut r).poll_read(&mut cx, &mut buf) {
+                    Poll::Ready(Ok(n)) => assert_eq!(*expected, n),
+                    other => panic!("unexpected: {:?}", other),
+                }
+            }
+        });
+    }
+
+    /// A `Timer` whose sleeps all complete when it is `fire`d, and not before.
+    #[derive(Clone, Default)]
+    struct MockTimer {
+        state: Arc<Mutex<(bool, Vec<Waker>)>>,
+    }
+
+    impl MockTimer {
+        fn fire(&self) {
+            let mut state = self.state.lock().unwrap();
+            state.0 = true;
+            state.1.drain(..).for_each(Waker::wake);
+        }
+    }
+
+    impl Timer for MockTimer {
+        fn sleep(&self, _duration: Duration) -> Sleep {
+            let state = self.state.clone();
+            Box::pin(future::poll_fn(move |cx| {
+                let mut state = state.lock().unwrap();
+                if state.0 {
+                    return Poll::Ready(());
+                }
+                state.1.push(cx.waker().clone());
+                Poll::Pending
+            }))
+        }
+    }
+
+    #[test]
+    fn mock_timer() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let timer = MockTimer::default();
+        let mut r = DelayFirst::new(io::Cursor::new(b"ab"), Duration::from_secs(3600));
+        r.set_timer(Arc::new(timer.clone()));
+
+        let mut buf = [0u8; 2];
+        assert!(Pin::new(&mut r).poll_read(&mut cx, &mut buf).is_pending());
+        assert!(Pin::new(&mut r).poll_read(&mut cx, &mut buf).is_pending());
+        timer.fire();
+        match Pin::new(&mut r).poll_read(&mut cx, &mut buf) {
+            Poll::Ready(Ok(2)) => assert_eq!(b"ab", &buf),
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+}
@@ -1,17 +1,34 @@
+use std::convert::TryInto;
+use std::fmt;
 use std::io;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
 
 use futures::future::poll_fn;
+use futures::io::AsyncReadExt as _;
+use futures::io::Chain;
 use futures::io::IoSlice;
+use futures::io::IoSliceMut;
 use futures::ready;
+use futures::stream;
 use futures::task::Context;
 use futures::task::Poll;
+use futures::task::Waker;
 use futures::AsyncBufRead;
 use futures::AsyncRead;
 use futures::AsyncWrite;
+use futures::Stream;
 use pin_project_lite::pin_project;
 use slice_deque::SliceDeque;
 
+use crate::json;
+use crate::label;
+use crate::memory_budget::Charge;
+use crate::timer::Deadline;
+use crate::MemoryBudget;
+use crate::Timer;
+
 pin_project! {
     /// An interface like `io::BufReader`, but extra data can be *repeatedly* added.
     ///
@@ -39,6 +56,64 @@ pin_project! {
         #[pin]
         inner: R,
         buf: SliceDeque<u8>,
+        timeout: Option<Duration>,
+        stalled: Option<Deadline>,
+        bypass: bool,
+        waker: Option<Waker>,
+        window: Option<usize>,
+        label: Option<&'static str>,
+        fill_target: usize,
+        write_closed: bool,
+        line: usize,
+        blocked: bool,
+        read_sizes: (usize, usize),
+        first_read: bool,
+        budget: Option<Charge>,
+        timer: Option<Arc<dyn Timer>>,
+        inner_reads: u64,
+        prime_on_empty: bool,
+        leak_check: Option<usize>,
+        consumed: bool,
+        high_water_mark: usize,
+        max_frame_len: usize,
+        // Space for reads larger than fit on the stack, kept between reads.
+        scratch: Vec<u8>,
+    }
+}
+
+/// The outcome of one step of a parser run by `DequeReader::drive`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step<T> {
+    /// The buffer does not contain enough data to make progress; read more.
+    NeedMore,
+    /// Discard this many bytes from the front of the buffer, then step again.
+    /// Consuming zero bytes is treated as `NeedMore`.
+    Consume(usize),
+    /// Parsing is complete: discard this many bytes, and return the value.
+    Done(usize, T),
+}
+
+/// An event from `DequeReader::events`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillEvent {
+    /// This many bytes arrived.
+    Data(usize),
+    /// The stream ended.
+    Eof,
+}
+
+/// Update the budget, if any, with the amount of data now buffered.
+fn settle(budget: &mut Option<Charge>, len: usize) {
+    if let Some(charge) = budget {
+        charge.settle(len);
+    }
+}
+
+/// Discard the oldest data, if we are buffering more than the window allows.
+fn trim_window(buf: &mut SliceDeque<u8>, window: Option<usize>) {
+    if let Some(window) = window {
+        let excess = buf.len().saturating_sub(window);
+        buf.drain(..excess);
     }
 }
 
@@ -56,9 +131,198 @@ impl<R> DequeReader<R> {
         DequeReader {
             inner,
             buf: SliceDeque::with_capacity(n),
+            timeout: None,
+            stalled: None,
+            bypass: true,
+            waker: None,
+            window: None,
+            label: None,
+            fill_target: 0,
+            write_closed: false,
+            line: 0,
+            blocked: false,
+            read_sizes: (4096, 4096),
+            first_read: true,
+            budget: None,
+            timer: None,
+            inner_reads: 0,
+            prime_on_empty: false,
+            leak_check: None,
+            consumed: false,
+            high_water_mark: 0,
+            max_frame_len: 16 * 1024 * 1024,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Fail reads with `TimedOut` if the inner reader makes no progress for `timeout`.
+    ///
+    /// The timer starts when the inner reader first returns `Pending`, and is reset whenever
+    /// it returns anything else.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> DequeReader<R> {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Keep at most the most recent `window` bytes buffered.
+    ///
+    /// When more data arrives, the oldest data is discarded, so `buffer()` always holds
+    /// the last `window` bytes read.
+    pub fn with_window(mut self, window: usize) -> DequeReader<R> {
+        self.window = Some(window);
+        self
+    }
+
+    /// Ask the inner reader for at most `first` bytes on the first read by `poll_read_more`,
+    /// and at most `rest` bytes on every read after that, e.g. to fetch a small header
+    /// quickly, followed by a large body. Both are `4096` by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either size is zero.
+    pub fn with_read_sizes(mut self, first: usize, rest: usize) -> DequeReader<R> {
+        assert!(first > 0 && rest > 0, "read sizes must be non-zero");
+        self.read_sizes = (first, rest);
+        self
+    }
+
+    /// Put `prefix` at the front of the buffer, so it is read before anything else,
+    /// e.g. to replay bytes which were read to sniff the protocol.
+    pub fn with_prefix(mut self, prefix: Vec<u8>) -> DequeReader<R> {
+        let mut buf = SliceDeque::with_capacity(prefix.len() + self.buf.len());
+        buf.extend_from_slice(&prefix);
+        buf.extend_from_slice(&self.buf);
+        self.buf = buf;
+        settle(&mut self.budget, self.buf.len());
+        self
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in errors generated
+    /// by this reader, to help identify which of many readers failed.
+    pub fn with_label(mut self, label: &'static str) -> DequeReader<R> {
+        self.label = Some(label);
+        self
+    }
+
+    /// Share a `MemoryBudget` with other readers, limiting how much they may buffer, in total.
+    ///
+    /// Reads from the inner reader are shortened to fit in the remaining budget, and wait
+    /// for other readers to consume data when it is exhausted. Reads which bypass the buffer,
+    /// and data added with `push`, are not limited, but data added by `push` is counted.
+    pub fn with_budget(mut self, budget: MemoryBudget) -> DequeReader<R> {
+        let mut charge = Charge::new(budget);
+        charge.settle(self.buf.len());
+        self.budget = Some(charge);
+        self
+    }
+
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    fn error(&self, kind: io::ErrorKind, msg: &str) -> io::Error {
+        label::error(self.label, kind, msg)
+    }
+
+    /// Control whether large reads skip the internal buffer, when it is empty. Enabled by default.
+    ///
+    /// With the bypass disabled, all reads are served from the internal buffer, so the
+    /// sizes requested from the inner reader do not depend on the caller's buffer size.
+    pub fn set_bypass(&mut self, enabled: bool) {
+        self.bypass = enabled;
+    }
+
+    /// Control whether a `poll_read` into an empty buffer, which some callers use to probe for
+    /// readiness, fills the internal buffer, if it is empty. Disabled by default.
+    ///
+    /// Either way, such a read returns `Ok(0)`, but when enabled, it only does so once data
+    /// has been buffered, or the stream has ended, returning `Pending` until then.
+    pub fn set_prime_on_empty(&mut self, enabled: bool) {
+        self.prime_on_empty = enabled;
+    }
+
+    /// Catch consumers which keep reading without consuming, so the buffer grows without
+    /// limit: once more than `threshold` bytes are buffered, reading more from the inner
+    /// reader panics, unless something has been consumed since the previous read.
+    /// Disabled by default.
+    pub fn set_leak_check(&mut self, threshold: usize) {
+        self.leak_check = Some(threshold);
+    }
+
+    /// The longest frame payload or body which `read_checked_frame` and `read_framed_with`
+    /// will accept from the peer, before buffering it. 16MiB by default.
+    pub fn set_max_frame_len(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+
+    /// When `poll_fill_buf` finds the buffer empty, keep reading until at least `target` bytes
+    /// are buffered, instead of stopping after one read. Disabled (zero) by default.
+    ///
+    /// Reading stops early at the end of the stream, or when the inner reader returns `Pending`
+    /// after some data has been buffered, so `poll_fill_buf` never waits for the target.
+    pub fn set_fill_target(&mut self, target: usize) {
+        self.fill_target = target;
+    }
+
+    /// The space allocated for the buffer which is not in use, for monitoring the memory
+    /// use of long-lived readers.
+    ///
+    /// The buffer is a ring, so consuming data from the front does not waste space: all
+    /// of the unused capacity is available for new data.
+    pub fn slack(&self) -> usize {
+        self.buf.capacity() - self.buf.len()
+    }
+
+    /// Use this `Timer` for timeouts, instead of the default `ThreadTimer`.
+    pub fn set_timer(&mut self, timer: Arc<dyn Timer>) {
+        self.timer = Some(timer);
+    }
+
+    /// Returns `true` if the last read from the inner reader returned `Pending`, and no
+    /// data has been added to the buffer since; i.e. reading more now would likely not
+    /// make progress, so the caller may wish to yield.
+    pub fn would_block(&self) -> bool {
+        self.blocked
+    }
+
+    /// How much to ask the inner reader for, next.
+    fn read_size(&self) -> usize {
+        if self.first_read {
+            self.read_sizes.0
+        } else {
+            self.read_sizes.1
+        }
+    }
+
+    /// Enforce `set_leak_check`, before reading more.
+    fn check_leaks(&self) {
+        if let Some(threshold) = self.leak_check {
+            assert!(
+                self.consumed || self.buf.len() <= threshold,
+                "read more with {} bytes buffered, without consuming any since the last read",
+                self.buf.len()
+            );
         }
     }
 
+    /// Discard the first `n` bytes of the buffer.
+    fn discard(&mut self, n: usize) {
+        self.buf.drain(..n);
+        self.consumed = true;
+        settle(&mut self.budget, self.buf.len());
+    }
+
+    /// How many times the inner reader has been polled for data, including polls which
+    /// returned `Pending`, and reads which bypassed the buffer.
+    pub fn inner_reads(&self) -> u64 {
+        self.inner_reads
+    }
+
+    /// The most data which has ever been buffered, e.g. to choose a size for `with_capacity`.
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
     /// Gets a reference to the underlying reader.
     ///
     /// It is inadvisable to directly read from the underlying reader.
@@ -80,35 +344,285 @@ impl<R> DequeReader<R> {
         self.project().inner
     }
 
+    /// Swap in a new underlying reader, e.g. a reconnected socket, returning the old one.
+    ///
+    /// The buffered data is kept, so parsing continues seamlessly from the old reader's data
+    /// into the new reader's. If a task is waiting for the old reader, it is woken, so it
+    /// can read from the new one.
+    pub fn replace_inner(&mut self, new: R) -> R {
+        self.stalled = None;
+        self.blocked = false;
+        self.write_closed = false;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+        std::mem::replace(&mut self.inner, new)
+    }
+
+    /// Discard the buffer, and any state from the old stream, and start reading from a
+    /// new underlying reader, e.g. to reuse a pooled reader, and its allocation, for a new
+    /// connection.
+    ///
+    /// Configuration, such as the label, timeouts, and budget, is kept.
+    pub fn reset(&mut self, new_inner: R) {
+        self.discard(self.buf.len());
+        self.line = 0;
+        self.first_read = true;
+        self.inner_reads = 0;
+        self.high_water_mark = 0;
+        self.replace_inner(new_inner);
+    }
+
+    /// Remove the first `n` bytes from the buffer, returning them as their own deque.
+    ///
+    /// No memory is shared between the two deques after the split: whichever part is
+    /// smaller is copied into a new allocation, and the larger part keeps the existing one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the buffer.
+    pub fn split_off_front(&mut self, n: usize) -> SliceDeque<u8> {
+        assert!(n <= self.buf.len(), "split point out of bounds");
+        if n <= self.buf.len() - n {
+            let mut front = SliceDeque::with_capacity(n);
+            front.extend_from_slice(&self.buf[..n]);
+            self.discard(n);
+            front
+        } else {
+            let rest = self.buf.split_off(n);
+            let front = std::mem::replace(&mut self.buf, rest);
+            self.consumed = true;
+            settle(&mut self.budget, self.buf.len());
+            front
+        }
+    }
+
+    /// Discard the last `amt` bytes of the buffer, e.g. an unwanted trailer which was read
+    /// along with the data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `amt` is greater than the length of the buffer.
+    pub fn consume_back(&mut self, amt: usize) {
+        assert!(amt <= self.buf.len(), "consumed more than the buffer");
+        self.buf.truncate(self.buf.len() - amt);
+        self.consumed = true;
+        settle(&mut self.budget, self.buf.len());
+    }
+
     /// Consumes this, returning the underlying reader.
     ///
     /// Note that any leftover data in the internal buffer is lost.
     pub fn into_inner(self) -> R {
         self.inner
     }
+
+    /// Consumes this, returning the buffered data, and the underlying reader, which
+    /// continues from the end of the buffered data, e.g. to hand off to different handlers.
+    pub fn split_buffered(self) -> (Vec<u8>, R) {
+        (self.buf.to_vec(), self.inner)
+    }
 }
 
 impl<R: AsyncRead> DequeReader<R> {
+    /// Read from the `inner` reader, applying the stall timeout, if any.
+    fn poll_inner(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        *this.inner_reads += 1;
+        // a single region gains nothing by itself, but reaches readers which only
+        // implement the vectored path efficiently
+        match this
+            .inner
+            .poll_read_vectored(cx, &mut [IoSliceMut::new(buf)])
+        {
+            Poll::Ready(r) => {
+                *this.stalled = None;
+                *this.waker = None;
+                *this.blocked = false;
+                Poll::Ready(r)
+            }
+            Poll::Pending => {
+                *this.blocked = true;
+                match this.waker {
+                    Some(waker) if waker.will_wake(cx.waker()) => (),
+                    _ => *this.waker = Some(cx.waker().clone()),
+                }
+                if let Some(timeout) = *this.timeout {
+                    let timer = this.timer.as_ref();
+                    let stalled = this
+                        .stalled
+                        .get_or_insert_with(|| Deadline::after(timer, timeout));
+                    if stalled.poll(cx).is_ready() {
+                        *this.stalled = None;
+                        return Poll::Ready(Err(label::error(
+                            *this.label,
+                            io::ErrorKind::TimedOut,
+                            "no progress from inner reader",
+                        )));
+                    }
+                }
+                Poll::Pending
+            }
+        }
+    }
+
     /// Attempt a large read against the `inner` reader.
     ///
+    /// The inner reader is read with `poll_read_vectored`, so readers with a faster
+    /// vectored path can use it; by default, this just calls `poll_read`.
+    ///
     /// If a byte could not be read as we are at the end of the stream, return `false`.
     pub fn poll_read_more(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<bool>> {
+        Poll::Ready(Ok(ready!(self.poll_read_chunk(cx, usize::MAX))? > 0))
+    }
+
+    /// `poll_read_more`, returning how many bytes were read, which is zero at the end of the stream.
+    fn poll_read_chunk(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        max: usize,
+    ) -> Poll<io::Result<usize>> {
+        self.check_leaks();
+        let mut size = self.read_size().min(max);
+        if let Some(charge) = self.as_mut().project().budget {
+            size = ready!(charge.poll_reserve(cx, size));
+        }
+        let found = self.as_mut().poll_inner_sized(cx, size, |this, data| {
+            let this = this.project();
+            *this.consumed = false;
+            this.buf.extend_from_slice(data);
+            trim_window(this.buf, *this.window);
+            *this.high_water_mark = (*this.high_water_mark).max(this.buf.len());
+            data.len()
+        });
+        // return any unused reservation
         let this = self.project();
-        let mut buf = [0u8; 4096];
-        let found = ready!(this.inner.poll_read(cx, &mut buf));
-        let found = match found {
-            Ok(n) => n,
-            Err(e) => return Poll::Ready(Err(e)),
+        settle(this.budget, this.buf.len());
+        found
+    }
+
+    /// Read at most `size` bytes from the inner reader, into a buffer on the stack, or the
+    /// scratch buffer, then hand them to `deliver`.
+    fn poll_inner_sized<T>(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        size: usize,
+        deliver: impl FnOnce(Pin<&mut Self>, &[u8]) -> T,
+    ) -> Poll<io::Result<T>> {
+        let mut stack = [0u8; 4096];
+        let mut scratch = std::mem::take(self.as_mut().project().scratch);
+        let buf = if size <= stack.len() {
+            &mut stack[..size]
+        } else {
+            if scratch.len() < size {
+                scratch.resize(size, 0);
+            }
+            &mut scratch[..size]
+        };
+        let result = match self.as_mut().poll_inner(cx, buf) {
+            Poll::Ready(Ok(found)) => {
+                *self.as_mut().project().first_read = false;
+                Poll::Ready(Ok(deliver(self.as_mut(), &buf[..found])))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
         };
-        let buf = &buf[..found];
-        this.buf.extend_from_slice(&buf);
-        Poll::Ready(Ok(!buf.is_empty()))
+        *self.project().scratch = scratch;
+        result
+    }
+
+    /// Read until at least `n` bytes are buffered, or the stream ends, then return
+    /// (up to) the first `n` bytes of the buffer, without consuming them.
+    pub fn poll_peek<'a>(
+        mut self: Pin<&'a mut Self>,
+        cx: &mut Context,
+        n: usize,
+    ) -> Poll<io::Result<&'a [u8]>> {
+        while self.buf.len() < n {
+            if !ready!(self.as_mut().poll_read_more(cx))? {
+                break;
+            }
+        }
+        let this = self.project();
+        let using = this.buf.len().min(n);
+        Poll::Ready(Ok(&this.buf.as_slice()[..using]))
     }
 
     /// Access the inner buffer directly, without attempting any reads.
     pub fn buffer(&self) -> &[u8] {
         self.buf.as_slice()
     }
+
+    /// The buffered data as up to two slices, like `VecDeque::as_slices`, for vectored
+    /// consumers which should not depend on the buffer being contiguous.
+    ///
+    /// Currently, the buffer is always contiguous, so the second slice is always empty.
+    pub fn buffer_regions(&self) -> (&[u8], &[u8]) {
+        (self.buf.as_slice(), &[])
+    }
+
+    /// The buffer, split into slices of `max` bytes, the last possibly shorter, e.g. for
+    /// writing to a sink with a size limit without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn buffer_chunks(&self, max: usize) -> impl Iterator<Item = &[u8]> {
+        self.buf.chunks(max)
+    }
+
+    /// Append data directly to the buffer, as if it had been read from the inner reader.
+    ///
+    /// If a task is waiting for the inner reader, it is woken, so it can see the new data.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+        trim_window(&mut self.buf, self.window);
+        self.high_water_mark = self.high_water_mark.max(self.buf.len());
+        settle(&mut self.budget, self.buf.len());
+        self.blocked = false;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// `push` each of the `inputs` in turn, after each calling `step` until it returns
+    /// `None`, collecting everything it returns, e.g. to check that a parser handles
+    /// data arriving in arbitrary pieces.
+    ///
+    /// `step` is responsible for consuming whatever it parses.
+    pub fn push_each<'i, T>(
+        &mut self,
+        inputs: impl IntoIterator<Item = &'i [u8]>,
+        mut step: impl FnMut(&mut Self) -> Option<T>,
+    ) -> Vec<T> {
+        let mut found = Vec::new();
+        for input in inputs {
+            self.push(input);
+            while let Some(value) = step(self) {
+                found.push(value);
+            }
+        }
+        found
+    }
+
+    /// A synchronous `io::Read` over the current `buffer()`, for handing to sync parsers.
+    ///
+    /// Reading advances only the returned cursor; the data remains buffered here until
+    /// `consume` is called separately.
+    pub fn buffered_cursor(&self) -> impl io::Read + '_ {
+        io::Cursor::new(self.buffer())
+    }
+
+    /// Consumes this, returning a plain reader of the buffered data, followed by the rest
+    /// of the stream from the underlying reader, as `split_buffered`, then `chain`.
+    pub fn into_chain(self) -> Chain<futures::io::Cursor<Vec<u8>>, R> {
+        let (buffered, inner) = self.split_buffered();
+        futures::io::Cursor::new(buffered).chain(inner)
+    }
 }
 
 impl<R: Unpin + AsyncRead> DequeReader<R> {
@@ -118,120 +632,2259 @@ impl<R: Unpin + AsyncRead> DequeReader<R> {
         // surely there's a more elegant way to write this
         poll_fn(|cx| Pin::new(&mut *self).poll_read_more(cx)).await
     }
-}
 
-impl<R: AsyncRead> AsyncRead for DequeReader<R> {
-    fn poll_read(
-        mut self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-        buf: &mut [u8],
-    ) -> Poll<io::Result<usize>> {
-        if buf.is_empty() {
-            return Poll::Ready(Ok(0));
-        }
+    /// Perform exactly one read against the `inner` reader, like `read_more`, returning
+    /// how many bytes were added to the buffer, which is zero at the end of the stream.
+    pub async fn read_chunk(&mut self) -> io::Result<usize> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_read_chunk(cx, usize::MAX)).await
+    }
 
-        if self.buf.is_empty() {
-            if buf.len() >= 4 * 1024 {
-                let this = self.project();
-                return this.inner.poll_read(cx, buf);
+    /// Wait for data, like `read_more`, then keep reading, without waiting, until `max`
+    /// bytes have been added to the buffer, or the inner reader has no more available,
+    /// returning how many bytes were added, which is zero at the end of the stream.
+    ///
+    /// As data was added, `would_block` is `false` afterwards, even if the last read
+    /// returned `Pending`.
+    pub async fn read_more_capped(&mut self, max: usize) -> io::Result<usize> {
+        if 0 == max {
+            return Ok(0);
+        }
+        let mut added = poll_fn(|cx| Pin::new(&mut *self).poll_read_chunk(cx, max)).await?;
+        if 0 == added {
+            return Ok(0);
+        }
+        while added < max {
+            let found =
+                poll_fn(|cx| Poll::Ready(Pin::new(&mut *self).poll_read_chunk(cx, max - added)))
+                    .await;
+            match found {
+                Poll::Ready(Ok(0)) => break,
+                Poll::Pending => {
+                    self.blocked = false;
+                    break;
+                }
+                Poll::Ready(Ok(n)) => added += n,
+                Poll::Ready(Err(e)) => return Err(e),
             }
+        }
+        Ok(added)
+    }
 
-            let _any_more = ready!(self.as_mut().poll_read_more(cx)?);
+    /// Read from the inner reader straight into the end of `dst`, instead of into the
+    /// buffer, returning how many bytes were added, which is zero at the end of the stream.
+    ///
+    /// If anything is already buffered, it is moved into `dst` instead, without reading,
+    /// so the data arrives in order. Data read straight into `dst` is not charged to the
+    /// `MemoryBudget`, if any, nor limited by the window, as it is never buffered here.
+    pub async fn read_more_into(&mut self, dst: &mut SliceDeque<u8>) -> io::Result<usize> {
+        if !self.buf.is_empty() {
+            let moved = self.buf.len();
+            dst.extend_from_slice(&self.buf);
+            self.discard(moved);
+            return Ok(moved);
         }
+        self.check_leaks();
+        let size = self.read_size();
+        let found = poll_fn(|cx| {
+            Pin::new(&mut *self).poll_inner_sized(cx, size, |_, data| {
+                dst.extend_from_slice(data);
+                data.len()
+            })
+        })
+        .await?;
+        self.consumed = false;
+        Ok(found)
+    }
 
-        let using = self.buf.len().min(buf.len());
-        buf[..using].copy_from_slice(&self.buf.as_slice()[..using]);
+    /// Returns `true` if the buffer is empty, and the inner reader is at the end of the stream.
+    ///
+    /// This may read more data into the buffer, but never consumes any.
+    pub async fn at_eof(&mut self) -> io::Result<bool> {
+        if !self.buf.is_empty() {
+            return Ok(false);
+        }
+        Ok(!self.read_more().await?)
+    }
 
-        let this = self.project();
-        this.buf.drain(..using);
+    /// Read the rest of the stream, including anything already buffered, into a `Vec`.
+    ///
+    /// The `Vec` is pre-sized from the buffer's capacity. This is named to avoid
+    /// shadowing `AsyncReadExt::read_to_end`, which also works here.
+    pub async fn read_to_vec(&mut self) -> io::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(self.buf.capacity().max(self.buf.len()));
+        loop {
+            out.extend_from_slice(&self.buf);
+            self.discard(self.buf.len());
+            if !self.read_more().await? {
+                return Ok(out);
+            }
+        }
+    }
 
-        Poll::Ready(Ok(using))
+    /// Discard the next `n` bytes of the stream, from the buffer and then the inner reader.
+    ///
+    /// Returns the number of bytes skipped, which is less than `n` only if the end of the
+    /// stream was reached.
+    pub async fn skip(&mut self, n: u64) -> io::Result<u64> {
+        let mut skipped = 0;
+        loop {
+            let using = (n - skipped).min(self.buf.len() as u64);
+            self.discard(using as usize);
+            skipped += using;
+            if skipped == n || !self.read_more().await? {
+                return Ok(skipped);
+            }
+        }
     }
-}
 
-impl<R: AsyncRead> AsyncBufRead for DequeReader<R> {
-    fn poll_fill_buf<'a>(
-        mut self: Pin<&'a mut Self>,
-        cx: &mut Context<'_>,
-    ) -> Poll<io::Result<&'a [u8]>> {
-        if self.buf.is_empty() {
-            let _any_more = ready!(self.as_mut().poll_read_more(cx))?;
+    /// Consume leading bytes which are in the `set`, reading more as needed, returning how
+    /// many were consumed. The first byte not in the `set` is left in the buffer.
+    pub async fn consume_while(&mut self, set: impl Fn(u8) -> bool) -> io::Result<usize> {
+        let mut consumed = 0;
+        loop {
+            let matching = self
+                .buf
+                .iter()
+                .position(|&b| !set(b))
+                .unwrap_or(self.buf.len());
+            self.discard(matching);
+            consumed += matching;
+            if !self.buf.is_empty() || !self.read_more().await? {
+                return Ok(consumed);
+            }
         }
-        let this = self.project();
-        Poll::Ready(Ok(this.buf.as_slice()))
     }
 
-    fn consume(self: Pin<&mut Self>, amt: usize) {
-        let this = self.project();
-        this.buf.drain(..amt);
+    /// Skip any leading separators, then read and consume a token, up to the next separator.
+    ///
+    /// The separator after the token is left in the buffer. Returns `None` if the end of the
+    /// stream is reached before a token starts.
+    pub async fn next_token(&mut self, is_sep: impl Fn(u8) -> bool) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let seps = self.buf.iter().take_while(|&&c| is_sep(c)).count();
+            self.discard(seps);
+            if !self.buf.is_empty() {
+                break;
+            }
+            if !self.read_more().await? {
+                return Ok(None);
+            }
+        }
+
+        let mut searched = 0;
+        loop {
+            if let Some(end) = self.buf[searched..].iter().position(|&c| is_sep(c)) {
+                let end = searched + end;
+                let token = self.buf[..end].to_vec();
+                self.discard(end);
+                return Ok(Some(token));
+            }
+            searched = self.buf.len();
+            if !self.read_more().await? {
+                let token = self.buf.to_vec();
+                self.discard(self.buf.len());
+                return Ok(Some(token));
+            }
+        }
     }
-}
 
-impl<W: AsyncWrite> AsyncWrite for DequeReader<W> {
-    fn poll_write(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-        buf: &[u8],
-    ) -> Poll<io::Result<usize>> {
-        self.project().inner.poll_write(cx, buf)
+    /// Read until at least `n` bytes are buffered, returning `false` if the stream ends first.
+    async fn fill_to(&mut self, n: usize) -> io::Result<bool> {
+        while self.buf.len() < n {
+            if !self.read_more().await? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
     }
 
-    fn poll_write_vectored(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-        bufs: &[IoSlice<'_>],
-    ) -> Poll<io::Result<usize>> {
-        self.project().inner.poll_write_vectored(cx, bufs)
+    /// Read until at least `n` bytes are buffered, returning the buffer.
+    ///
+    /// If the stream ends first, the shorter buffer is returned. If `timeout` passes first,
+    /// `TimedOut` is returned, and any data read so far remains buffered.
+    pub async fn fill_at_least_timeout(
+        &mut self,
+        n: usize,
+        timeout: Duration,
+    ) -> io::Result<&[u8]> {
+        let mut deadline = Deadline::after(self.timer.as_ref(), timeout);
+        poll_fn(|cx| loop {
+            if self.buf.len() >= n {
+                return Poll::Ready(Ok(()));
+            }
+            match Pin::new(&mut *self).poll_read_more(cx)? {
+                Poll::Ready(true) => (),
+                Poll::Ready(false) => return Poll::Ready(Ok(())),
+                Poll::Pending => {
+                    ready!(deadline.poll(cx));
+                    return Poll::Ready(Err(
+                        self.error(io::ErrorKind::TimedOut, "buffer not filled in time")
+                    ));
+                }
+            }
+        })
+        .await?;
+        Ok(self.buffer())
     }
 
-    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        self.project().inner.poll_flush(cx)
+    /// Borrow this as a reader of only the next `n` bytes, e.g. to hand a length-delimited
+    /// region to a parser which reads until EOF.
+    pub fn limited(&mut self, n: u64) -> LimitedDequeReader<'_, R> {
+        LimitedDequeReader {
+            reader: self,
+            remaining: n,
+        }
     }
 
-    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        self.project().inner.poll_close(cx)
+    /// A future which resolves once at least `watermark` bytes are buffered, reading as
+    /// needed, or fails with `UnexpectedEof` if the stream ends first.
+    pub async fn wait_for(&mut self, watermark: usize) -> io::Result<()> {
+        if !self.fill_to(watermark).await? {
+            return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+        }
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::pin::Pin;
+    /// Read and consume enough bytes to fill an array, failing with `UnexpectedEof` on a short stream.
+    async fn read_array<A: Default + AsMut<[u8]>>(&mut self) -> io::Result<A> {
+        let mut array = A::default();
+        let len = array.as_mut().len();
+        if !self.fill_to(len).await? {
+            return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+        }
+        array.as_mut().copy_from_slice(&self.buf[..len]);
+        self.discard(len);
+        Ok(array)
+    }
 
-    use async_std::task;
-    use futures::io;
-    use futures::io::AsyncBufRead;
+    /// Read and consume a single byte, failing with `UnexpectedEof` at the end of the stream.
+    pub async fn read_u8(&mut self) -> io::Result<u8> {
+        let [b] = self.read_array::<[u8; 1]>().await?;
+        Ok(b)
+    }
 
-    use crate::DequeReader;
-    use crate::ShortRead;
+    /// Read and consume a big-endian `u16`, failing with `UnexpectedEof` on a short stream.
+    pub async fn read_u16_be(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.read_array().await?))
+    }
 
-    #[test]
-    fn buf_read() {
-        task::block_on(async {
-            let mut m = DequeReader::new(ShortRead::new(
-                io::Cursor::new(b"hello world"),
-                vec![2, 3, 4, 5].into_iter(),
-            ));
-            assert_eq!(b"", m.buffer());
-            assert_eq!(true, m.read_more().await.unwrap());
-            assert_eq!(b"he", m.buffer());
-            assert_eq!(true, m.read_more().await.unwrap());
-            assert_eq!(b"hello", m.buffer());
+    /// Read and consume a little-endian `u16`, failing with `UnexpectedEof` on a short stream.
+    pub async fn read_u16_le(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.read_array().await?))
+    }
 
-            Pin::new(&mut m).consume(2);
-            assert_eq!(b"llo", m.buffer());
+    /// Read and consume a big-endian `u32`, failing with `UnexpectedEof` on a short stream.
+    pub async fn read_u32_be(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.read_array().await?))
+    }
 
-            assert_eq!(true, m.read_more().await.unwrap());
-            assert_eq!(b"llo wor", m.buffer());
+    /// Read and consume a little-endian `u32`, failing with `UnexpectedEof` on a short stream.
+    pub async fn read_u32_le(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.read_array().await?))
+    }
 
-            Pin::new(&mut m).consume(1);
-            assert_eq!(b"lo wor", m.buffer());
+    /// Read and consume a big-endian `u64`, failing with `UnexpectedEof` on a short stream.
+    pub async fn read_u64_be(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(self.read_array().await?))
+    }
 
-            assert_eq!(true, m.read_more().await.unwrap());
-            assert_eq!(b"lo world", m.buffer());
+    /// Read and consume a little-endian `u64`, failing with `UnexpectedEof` on a short stream.
+    pub async fn read_u64_le(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.read_array().await?))
+    }
 
-            assert_eq!(false, m.read_more().await.unwrap());
-            assert_eq!(b"lo world", m.buffer());
-            assert_eq!(false, m.read_more().await.unwrap());
+    /// Read a frame: a big-endian `u32` length, the payload, then the big-endian `u32`
+    /// CRC-32 of the payload, as computed by `crc32`.
+    ///
+    /// See `read_checked_frame_with`.
+    pub async fn read_checked_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.read_checked_frame_with(crate::crc32).await
+    }
+
+    /// Read a frame, as `read_checked_frame`, but with a custom checksum function.
+    ///
+    /// Returns `None` if the stream ends with nothing buffered, and `UnexpectedEof` if it ends
+    /// in the middle of a frame. If the checksum does not match, the frame is consumed, and
+    /// `InvalidData` is returned.
+    ///
+    /// Fails with `InvalidData`, leaving the stream buffered, if the frame's length is over
+    /// the limit set by `set_max_frame_len`.
+    pub async fn read_checked_frame_with<F>(&mut self, checksum: F) -> io::Result<Option<Vec<u8>>>
+    where
+        F: FnOnce(&[u8]) -> u32,
+    {
+        if !self.fill_to(4).await? {
+            if self.buf.is_empty() {
+                return Ok(None);
+            }
+            return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+        }
+        let len = u32::from_be_bytes(self.buf[..4].try_into().expect("sliced")) as usize;
+        let end = match len.checked_add(4 + 4) {
+            Some(end) if len <= self.max_frame_len => end,
+            _ => return Err(self.error(io::ErrorKind::InvalidData, "frame too long")),
+        };
+        if !self.fill_to(end).await? {
+            return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+        }
+        let payload = self.buf[4..4 + len].to_vec();
+        let expected = u32::from_be_bytes(self.buf[4 + len..end].try_into().expect("sliced"));
+        self.discard(end);
+        if checksum(&payload) != expected {
+            return Err(self.error(io::ErrorKind::InvalidData, "frame checksum mismatch"));
+        }
+        Ok(Some(payload))
+    }
+
+    /// Read a frame with a custom header: `parse_len` decodes the first `header_len` bytes
+    /// into a value, and the length of the body which follows.
+    ///
+    /// The header and body are consumed together, once both are buffered, and the header's
+    /// value is returned with the body. Fails with `UnexpectedEof` if the stream ends first,
+    /// or with `InvalidData` if the body's length is over the limit set by
+    /// `set_max_frame_len`, leaving the buffer intact.
+    pub async fn read_framed_with<F, T>(
+        &mut self,
+        header_len: usize,
+        parse_len: F,
+    ) -> io::Result<(T, Vec<u8>)>
+    where
+        F: FnOnce(&[u8]) -> (T, usize),
+    {
+        if !self.fill_to(header_len).await? {
+            return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+        }
+        let (header, len) = parse_len(&self.buf[..header_len]);
+        let end = match header_len.checked_add(len) {
+            Some(end) if len <= self.max_frame_len => end,
+            _ => return Err(self.error(io::ErrorKind::InvalidData, "frame too long")),
+        };
+        if !self.fill_to(end).await? {
+            return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+        }
+        let body = self.buf[header_len..end].to_vec();
+        self.discard(end);
+        Ok((header, body))
+    }
+
+    /// Read and consume `magic`, e.g. the signature at the start of a file format.
+    ///
+    /// Fails with `InvalidData` if the stream starts with anything else, leaving it buffered,
+    /// or with `UnexpectedEof` if the stream is shorter than `magic`.
+    pub async fn expect_magic(&mut self, magic: &[u8]) -> io::Result<()> {
+        if !self.fill_to(magic.len()).await? {
+            return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+        }
+        if &self.buf[..magic.len()] != magic {
+            return Err(self.error(io::ErrorKind::InvalidData, "magic mismatch"));
+        }
+        self.discard(magic.len());
+        Ok(())
+    }
+
+    /// Read and consume `literal`, e.g. a delimiter where a protocol expects one.
+    ///
+    /// Fails with `InvalidData`, giving the position of the first difference, as soon as the
+    /// stream differs, leaving it buffered, or with `UnexpectedEof` if the stream ends first.
+    pub async fn expect(&mut self, literal: &[u8]) -> io::Result<()> {
+        loop {
+            let available = self.buf.len().min(literal.len());
+            if let Some(pos) = (0..available).find(|&i| self.buf[i] != literal[i]) {
+                return Err(self.error(
+                    io::ErrorKind::InvalidData,
+                    &format!("expected literal differs at byte {}", pos),
+                ));
+            }
+            if available == literal.len() {
+                self.discard(available);
+                return Ok(());
+            }
+            if !self.read_more().await? {
+                return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+            }
+        }
+    }
+
+    /// Consume the line returned by the previous call, if any, then read the next line,
+    /// returning it without its `\n`, and without allocating.
+    ///
+    /// The returned line borrows this reader, so only one line can be live at a time:
+    ///
+    /// ```rust
+    /// # use futures::io;
+    /// # async_std::task::block_on(async {
+    /// let mut m = aiowrap::DequeReader::new(io::Cursor::new(b"one\ntwo"));
+    /// while let Some(line) = m.advance_line().await.unwrap() {
+    ///     assert_eq!(3, line.len());
+    /// }
+    /// # });
+    /// ```
+    ///
+    /// The final line need not end with a `\n`. Returns `None` at the end of the stream.
+    /// The line remains in the buffer until the next call, so other methods which consume
+    /// from the buffer should not be used in between.
+    pub async fn advance_line(&mut self) -> io::Result<Option<&[u8]>> {
+        let previous = self.line.min(self.buf.len());
+        self.discard(previous);
+        self.line = 0;
+
+        let mut searched = 0;
+        loop {
+            if let Some(end) = self.buf[searched..].iter().position(|&c| c == b'\n') {
+                let end = searched + end;
+                self.line = end + 1;
+                return Ok(Some(&self.buf[..end]));
+            }
+            searched = self.buf.len();
+            if !self.read_more().await? {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                self.line = self.buf.len();
+                return Ok(Some(&self.buf[..]));
+            }
+        }
+    }
+
+    /// Read until `byte` is buffered, returning its index in the buffer, without consuming
+    /// anything, or `None` if the stream ends first.
+    pub async fn find(&mut self, byte: u8) -> io::Result<Option<usize>> {
+        let mut searched = 0;
+        loop {
+            if let Some(end) = self.buf[searched..].iter().position(|&c| c == byte) {
+                return Ok(Some(searched + end));
+            }
+            searched = self.buf.len();
+            if !self.read_more().await? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Read until `needle` is buffered, then consume and return everything up to, and
+    /// including, it, e.g. the headers of an HTTP request, up to `\r\n\r\n`.
+    ///
+    /// Returns `None` if the stream ends first, leaving the data buffered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `needle` is empty.
+    pub async fn consume_through_seq(&mut self, needle: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        assert!(!needle.is_empty(), "needle must be non-empty");
+        let mut searched = 0;
+        loop {
+            if let Some(start) = self.buf[searched..]
+                .windows(needle.len())
+                .position(|w| w == needle)
+            {
+                let end = searched + start + needle.len();
+                let found = self.buf[..end].to_vec();
+                self.discard(end);
+                return Ok(Some(found));
+            }
+            // the needle may start in the last few bytes searched
+            searched = self.buf.len().saturating_sub(needle.len() - 1);
+            if !self.read_more().await? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Read until a whole line is buffered, then return it, including the `\n`, without
+    /// consuming it; `consume` its length to accept it.
+    ///
+    /// The final line need not end with a `\n`. Returns `None` at the end of the stream.
+    pub async fn peek_line(&mut self) -> io::Result<Option<&[u8]>> {
+        match self.find(b'\n').await? {
+            Some(end) => Ok(Some(&self.buf[..=end])),
+            None if self.buf.is_empty() => Ok(None),
+            None => Ok(Some(&self.buf[..])),
+        }
+    }
+
+    /// Read and consume a NUL-terminated string, returning it without the NUL.
+    ///
+    /// Returns `None` if the stream ends with nothing buffered, and `UnexpectedEof` if
+    /// it ends in the middle of a string.
+    pub async fn read_cstr(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match self.find(0).await? {
+            Some(end) => {
+                let s = self.buf[..end].to_vec();
+                self.discard(end + 1);
+                Ok(Some(s))
+            }
+            None if self.buf.is_empty() => Ok(None),
+            None => Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended inside a string")),
+        }
+    }
+
+    /// Read and consume one complete JSON value, returning its bytes to be handed to a real
+    /// JSON parser. Leading whitespace, and commas, are skipped, so a sequence of values
+    /// separated by commas can be read by calling this repeatedly.
+    ///
+    /// The value is not validated: only brackets, braces, and strings (including escapes)
+    /// are tracked, to find where the value ends. A bare top-level scalar, like a number,
+    /// ends at the next whitespace or delimiter, or the end of the stream.
+    ///
+    /// Returns `None` if the stream ends with only whitespace buffered, and `UnexpectedEof`
+    /// if it ends in the middle of a value. A value starting with any other delimiter, such
+    /// as `:` or `]`, is `InvalidData`.
+    pub async fn read_json_value(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut scan = json::Scan::default();
+        loop {
+            let start = self
+                .buf
+                .iter()
+                .position(|b| !b" \t\r\n,".contains(b))
+                .unwrap_or(self.buf.len());
+            self.discard(start);
+
+            let end = scan
+                .scan(&self.buf)
+                .map_err(|msg| self.error(io::ErrorKind::InvalidData, msg))?;
+            if let Some(end) = end {
+                let value = self.buf[..end].to_vec();
+                self.discard(end);
+                return Ok(Some(value));
+            }
+
+            if !self.read_more().await? {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                if !scan.end_of_stream() {
+                    return Err(
+                        self.error(io::ErrorKind::UnexpectedEof, "stream ended inside a value")
+                    );
+                }
+                let value = self.buf.to_vec();
+                self.discard(self.buf.len());
+                return Ok(Some(value));
+            }
+        }
+    }
+
+    /// Convert into a `Stream` of the data, as it arrives, in chunks of at most `chunk` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` is zero.
+    pub fn into_chunk_stream(mut self, chunk: usize) -> impl Stream<Item = io::Result<Vec<u8>>> {
+        assert!(chunk > 0, "chunks must be non-empty");
+        stream::poll_fn(move |cx| {
+            if self.buf.is_empty() {
+                match ready!(Pin::new(&mut self).poll_read_more(cx)) {
+                    Ok(true) => (),
+                    Ok(false) => return Poll::Ready(None),
+                    Err(e) => return Poll::Ready(Some(Err(e))),
+                }
+            }
+            let using = chunk.min(self.buf.len());
+            let out = self.buf[..using].to_vec();
+            self.discard(using);
+            Poll::Ready(Some(Ok(out)))
+        })
+    }
+
+    /// Convert into a `Stream` of the offset in the stream of every occurrence of `needle`,
+    /// including overlapping occurrences, e.g. to scan logs.
+    ///
+    /// Data is discarded once it has been searched, so memory use is bounded however long
+    /// the stream is. Offsets are from where the buffer starts now.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `needle` is empty.
+    pub fn matches(mut self, needle: Vec<u8>) -> impl Stream<Item = io::Result<u64>> {
+        assert!(!needle.is_empty(), "needle must be non-empty");
+        let mut base = 0u64;
+        stream::poll_fn(move |cx| loop {
+            if let Some(start) = self
+                .buf
+                .windows(needle.len())
+                .position(|w| w == &needle[..])
+            {
+                // a later occurrence may overlap this one, so only skip its first byte
+                self.discard(start + 1);
+                let found = base + start as u64;
+                base += start as u64 + 1;
+                return Poll::Ready(Some(Ok(found)));
+            }
+            let searched = self.buf.len().saturating_sub(needle.len() - 1);
+            self.discard(searched);
+            base += searched as u64;
+            match ready!(Pin::new(&mut self).poll_read_more(cx)) {
+                Ok(true) => (),
+                Ok(false) => return Poll::Ready(None),
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+        })
+    }
+
+    /// Convert into a `Stream` of events: how much data arrived from each read of the inner
+    /// reader, then the end of the stream, for code which only needs to react to activity.
+    ///
+    /// The data itself is discarded. Anything already buffered is reported first.
+    pub fn events(mut self) -> impl Stream<Item = io::Result<FillEvent>> {
+        let mut done = false;
+        stream::poll_fn(move |cx| {
+            if done {
+                return Poll::Ready(None);
+            }
+            let found = match self.buf.len() {
+                0 => ready!(Pin::new(&mut self).poll_read_chunk(cx, usize::MAX))?,
+                buffered => buffered,
+            };
+            self.discard(self.buf.len());
+            if 0 == found {
+                done = true;
+                return Poll::Ready(Some(Ok(FillEvent::Eof)));
+            }
+            Poll::Ready(Some(Ok(FillEvent::Data(found))))
+        })
+    }
+
+    /// Speculatively parse the buffer: `parse` returns the number of bytes it used, and the
+    /// value, or `None` if the buffer does not yet hold a complete value.
+    ///
+    /// On success, exactly the used bytes are consumed. Otherwise, nothing is consumed, and
+    /// more data is read before trying again. If the stream ends first, `None` is returned,
+    /// and the incomplete data is left in the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parse` claims to have used more bytes than it was given.
+    pub async fn try_parse<T, F>(&mut self, mut parse: F) -> io::Result<Option<T>>
+    where
+        F: FnMut(&[u8]) -> Option<(usize, T)>,
+    {
+        loop {
+            if let Some((used, value)) = parse(self.buffer()) {
+                assert!(used <= self.buf.len(), "parser used more than the buffer");
+                self.discard(used);
+                return Ok(Some(value));
+            }
+            if !self.read_more().await? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Repeatedly call `step` with the buffer, reading and consuming as it requests,
+    /// until it returns `Step::Done`.
+    ///
+    /// If `step` needs more data but we are at the end of the stream, `UnexpectedEof` is returned.
+    /// If it asks to discard more than is buffered, `InvalidInput` is returned, and nothing
+    /// is discarded.
+    pub async fn drive<T, F>(&mut self, mut step: F) -> io::Result<T>
+    where
+        F: FnMut(&[u8]) -> Step<T>,
+    {
+        loop {
+            match step(self.buffer()) {
+                Step::NeedMore | Step::Consume(0) => {
+                    if !self.read_more().await? {
+                        return Err(self.error(io::ErrorKind::UnexpectedEof, "stream ended early"));
+                    }
+                }
+                Step::Consume(n) | Step::Done(n, _) if n > self.buf.len() => {
+                    return Err(self.error(
+                        io::ErrorKind::InvalidInput,
+                        "step used more than the buffer",
+                    ));
+                }
+                Step::Consume(n) => {
+                    self.discard(n);
+                }
+                Step::Done(n, value) => {
+                    self.discard(n);
+                    return Ok(value);
+                }
+            }
+        }
+    }
+}
+
+/// A view of the next `n` bytes of a `DequeReader`, from `DequeReader::limited`.
+///
+/// This reads from the parent's buffer, then its inner reader, then reports EOF once
+/// `n` bytes have been read. Any data read from the inner reader past the end of the
+/// region stays buffered in the parent, so it continues from just after what this read.
+pub struct LimitedDequeReader<'a, R> {
+    reader: &'a mut DequeReader<R>,
+    remaining: u64,
+}
+
+impl<'a, R> LimitedDequeReader<'a, R> {
+    /// How much of the region has not been read yet.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncRead for LimitedDequeReader<'a, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let using = (buf.len() as u64).min(self.remaining) as usize;
+        if 0 == using {
+            return Poll::Ready(Ok(0));
+        }
+        let found = ready!(Pin::new(&mut *self.reader).poll_read(cx, &mut buf[..using]))?;
+        self.remaining -= found as u64;
+        Poll::Ready(Ok(found))
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncBufRead for LimitedDequeReader<'a, R> {
+    fn poll_fill_buf<'b>(
+        self: Pin<&'b mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<&'b [u8]>> {
+        let this = self.get_mut();
+        if 0 == this.remaining {
+            return Poll::Ready(Ok(&[]));
+        }
+        let buf = ready!(Pin::new(&mut *this.reader).poll_fill_buf(cx))?;
+        let using = (buf.len() as u64).min(this.remaining) as usize;
+        Poll::Ready(Ok(&buf[..using]))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        assert!(
+            amt as u64 <= self.remaining,
+            "consumed past the end of the region"
+        );
+        Pin::new(&mut *self.reader).consume(amt);
+        self.remaining -= amt as u64;
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for DequeReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DequeReader")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for DequeReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            if self.prime_on_empty && self.buf.is_empty() {
+                let _any_more = ready!(self.poll_read_more(cx))?;
+            }
+            return Poll::Ready(Ok(0));
+        }
+
+        if self.buf.is_empty() {
+            if self.bypass && buf.len() >= 4 * 1024 {
+                return self.poll_inner(cx, buf);
+            }
+
+            let _any_more = ready!(self.as_mut().poll_read_more(cx)?);
+        }
+
+        let using = self.buf.len().min(buf.len());
+        buf[..using].copy_from_slice(&self.buf.as_slice()[..using]);
+
+        let this = self.project();
+        this.buf.drain(..using);
+        *this.consumed = true;
+        settle(this.budget, this.buf.len());
+
+        Poll::Ready(Ok(using))
+    }
+}
+
+impl<R: AsyncRead> AsyncBufRead for DequeReader<R> {
+    fn poll_fill_buf<'a>(
+        mut self: Pin<&'a mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<&'a [u8]>> {
+        if self.buf.is_empty() {
+            let _any_more = ready!(self.as_mut().poll_read_more(cx))?;
+            while self.buf.len() < self.fill_target {
+                match self.as_mut().poll_read_more(cx)? {
+                    Poll::Ready(true) => (),
+                    Poll::Ready(false) => break,
+                    Poll::Pending => {
+                        // we're returning data, so this isn't a stall
+                        *self.as_mut().project().stalled = None;
+                        break;
+                    }
+                }
+            }
+        }
+        let this = self.project();
+        Poll::Ready(Ok(this.buf.as_slice()))
+    }
+
+    /// `poll_fill_buf` always returns the whole buffer, so this checks `amt` against what is
+    /// buffered now, which is what `poll_fill_buf` last returned, plus anything read since by
+    /// methods like `read_more`, whose callers see it through `buffer`.
+    ///
+    /// Consuming more than that breaks the `AsyncBufRead` contract: it panics in debug
+    /// builds, to catch bugs in decoders, and consumes everything in release builds.
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.project();
+        debug_assert!(
+            amt <= this.buf.len(),
+            "consumed {} bytes, but only {} are buffered",
+            amt,
+            this.buf.len()
+        );
+        let amt = amt.min(this.buf.len());
+        this.buf.drain(..amt);
+        *this.consumed = true;
+        settle(this.budget, this.buf.len());
+    }
+}
+
+impl<W: Unpin + AsyncWrite> DequeReader<W> {
+    /// Flush and close the write side of the inner stream, resolving once it is fully closed.
+    ///
+    /// Any further writes fail with `BrokenPipe`.
+    pub async fn shutdown(&mut self) -> io::Result<()> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_flush(cx)).await?;
+        poll_fn(|cx| Pin::new(&mut *self).poll_close(cx)).await
+    }
+
+    /// Resolves when the write side of the inner stream is likely to accept more data,
+    /// e.g. to wait for backpressure to clear before preparing a large write.
+    ///
+    /// `AsyncWrite` has no readiness API, so this is approximated by a zero-length write to
+    /// the inner writer. Many writers complete zero-length writes immediately, whether or not
+    /// they have space, in which case this also resolves immediately.
+    pub async fn writable(&mut self) -> io::Result<()> {
+        self.check_write_open()?;
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_write(cx, &[])).await?;
+        Ok(())
+    }
+}
+
+impl<W> DequeReader<W> {
+    fn check_write_open(&self) -> io::Result<()> {
+        if self.write_closed {
+            return Err(self.error(io::ErrorKind::BrokenPipe, "write after close"));
+        }
+        Ok(())
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for DequeReader<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.check_write_open()?;
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.check_write_open()?;
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        ready!(this.inner.poll_close(cx))?;
+        *this.write_closed = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::Future;
+    use std::marker::PhantomPinned;
+    use std::pin::Pin;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncBufRead;
+    use futures::io::AsyncBufReadExt as _;
+    use futures::io::AsyncRead;
+    use futures::io::AsyncReadExt as _;
+    use futures::io::AsyncWriteExt as _;
+    use futures::io::IoSliceMut;
+    use futures::stream::TryStreamExt as _;
+    use futures::task::ArcWake;
+    use futures::task::Context;
+    use futures::task::Poll;
+    use slice_deque::SliceDeque;
+
+    use crate::DequeReader;
+    use crate::FillEvent;
+    use crate::MemoryBudget;
+    use crate::ShortRead;
+    use crate::StallWrite;
+    use crate::Step;
+
+    /// A hung peer: never produces data, and never wakes the task.
+    struct PendingForever;
+
+    impl AsyncRead for PendingForever {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+    }
+
+    /// Records whether it has been woken.
+    #[derive(Default)]
+    struct Flag(AtomicBool);
+
+    impl ArcWake for Flag {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl Flag {
+        fn woken(&self) -> bool {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    #[test]
+    fn buf_read() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello world"),
+                vec![2, 3, 4, 5].into_iter(),
+            ));
+            assert_eq!(b"", m.buffer());
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"he", m.buffer());
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"hello", m.buffer());
+
+            Pin::new(&mut m).consume(2);
+            assert_eq!(b"llo", m.buffer());
+
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"llo wor", m.buffer());
+
+            Pin::new(&mut m).consume(1);
+            assert_eq!(b"lo wor", m.buffer());
+
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"lo world", m.buffer());
+
+            assert!(!m.read_more().await.unwrap());
+            assert_eq!(b"lo world", m.buffer());
+            assert!(!m.read_more().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn buffered_cursor() {
+        use std::io::Read as _;
+
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"hello world"));
+            assert!(m.read_more().await.unwrap());
+
+            let mut sync = m.buffered_cursor();
+            let mut buf = [0u8; 5];
+            sync.read_exact(&mut buf).unwrap();
+            assert_eq!(b"hello", &buf);
+            let mut rest = Vec::new();
+            sync.read_to_end(&mut rest).unwrap();
+            assert_eq!(b" world", rest.as_slice());
+
+            // nothing was consumed
+            assert_eq!(b"hello world", m.buffer());
+        });
+    }
+
+    #[test]
+    fn read_timeout() {
+        task::block_on(async {
+            let timeout = Duration::from_millis(50);
+            let mut m = DequeReader::new(PendingForever).with_read_timeout(timeout);
+            let start = Instant::now();
+            let err = m.read_more().await.unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+            assert!(start.elapsed() >= timeout);
+        });
+    }
+
+    /// Skip leading spaces, then parse a decimal number terminated by a space.
+    fn int(buf: &[u8]) -> Step<u64> {
+        let spaces = buf.iter().take_while(|&&c| c == b' ').count();
+        if spaces > 0 {
+            return Step::Consume(spaces);
+        }
+        let digits = buf.iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits == buf.len() {
+            return Step::NeedMore;
+        }
+        let value = std::str::from_utf8(&buf[..digits])
+            .unwrap()
+            .parse()
+            .unwrap();
+        Step::Done(digits, value)
+    }
+
+    #[test]
+    fn drive() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"  12 345 6"),
+                vec![1, 2, 1, 3, 1, 1, 1].into_iter(),
+            ));
+            assert_eq!(12, m.drive(int).await.unwrap());
+            assert_eq!(345, m.drive(int).await.unwrap());
+            assert_eq!(
+                io::ErrorKind::UnexpectedEof,
+                m.drive(int).await.unwrap_err().kind()
+            );
+        });
+    }
+
+    #[test]
+    fn drive_misbehaving() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"abc"));
+            let err = m.drive(|_| Step::<()>::Consume(0)).await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+
+            let mut m = DequeReader::new(io::Cursor::new(b"abc"));
+            assert!(m.read_more().await.unwrap());
+            let err = m
+                .drive(|buf| Step::Done(buf.len() + 1, ()))
+                .await
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+            assert_eq!(b"abc", m.buffer());
+        });
+    }
+
+    #[test]
+    fn at_eof() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"abc"),
+                vec![2, 0, 1].into_iter(),
+            ));
+            assert!(!m.at_eof().await.unwrap());
+            assert_eq!(b"ab", m.buffer());
+            Pin::new(&mut m).consume(2);
+            assert!(!m.at_eof().await.unwrap());
+            assert_eq!(b"c", m.buffer());
+            assert!(!m.at_eof().await.unwrap());
+            Pin::new(&mut m).consume(1);
+            assert!(m.at_eof().await.unwrap());
+            assert!(m.at_eof().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn split_off_front() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"abcdefgh"));
+            assert!(m.read_more().await.unwrap());
+
+            let frame = m.split_off_front(3);
+            assert_eq!(b"abc", frame.as_slice());
+            assert_eq!(b"defgh", m.buffer());
+
+            let frame = m.split_off_front(4);
+            assert_eq!(b"defg", frame.as_slice());
+            assert_eq!(b"h", m.buffer());
+
+            assert!(!m.read_more().await.unwrap());
+            assert_eq!(b"h", m.buffer());
+        });
+    }
+
+    #[test]
+    fn skip() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello world"),
+                vec![2, 0, 3, 4, 5].into_iter(),
+            ));
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(6, m.skip(6).await.unwrap());
+            let mut rest = Vec::new();
+            m.read_to_end(&mut rest).await.unwrap();
+            assert_eq!(b"world", rest.as_slice());
+
+            assert_eq!(0, m.skip(6).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn bypass() {
+        task::block_on(async {
+            let data = vec![7u8; 10_000];
+            let mut buf = vec![0u8; 8192];
+
+            let mut m = DequeReader::new(
+                ShortRead::new(io::Cursor::new(data.clone()), vec![5000, 3].into_iter())
+                    .recording(),
+            );
+            assert_eq!(5000, m.read(&mut buf).await.unwrap());
+            assert_eq!(&[5000], m.get_ref().produced_sizes());
+
+            let mut m = DequeReader::new(
+                ShortRead::new(io::Cursor::new(data), vec![5000, 3].into_iter()).recording(),
+            );
+            m.set_bypass(false);
+            assert_eq!(4096, m.read(&mut buf).await.unwrap());
+            assert_eq!(3, m.read(&mut buf).await.unwrap());
+            assert_eq!(&[4096, 3], m.get_ref().produced_sizes());
+        });
+    }
+
+    #[test]
+    fn push() {
+        let flag = Arc::new(Flag::default());
+        let waker = futures::task::waker(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut m = DequeReader::new(PendingForever);
+        let mut buf = [0u8; 16];
+        assert!(Pin::new(&mut m).poll_read(&mut cx, &mut buf).is_pending());
+        assert!(!flag.woken());
+
+        m.push(b"hi");
+        assert!(flag.woken());
+        match Pin::new(&mut m).poll_read(&mut cx, &mut buf) {
+            Poll::Ready(Ok(2)) => assert_eq!(b"hi", &buf[..2]),
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "consumed 6 bytes, but only 5 are buffered")]
+    fn over_consume() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"hello"));
+            assert_eq!(b"hello", m.fill_buf().await.unwrap());
+            Pin::new(&mut m).consume(6);
+        });
+    }
+
+    #[test]
+    fn push_each() {
+        fn line(m: &mut DequeReader<io::Empty>) -> Option<Vec<u8>> {
+            let end = m.buffer().iter().position(|&b| b'\n' == b)?;
+            let line = m.split_off_front(end + 1);
+            Some(line[..end].to_vec())
+        }
+
+        let input: &[u8] = b"one\ntwo\n\nthree\n";
+        let expected = vec![
+            b"one".to_vec(),
+            b"two".to_vec(),
+            Vec::new(),
+            b"three".to_vec(),
+        ];
+        for split in 0..=input.len() {
+            let (a, b) = input.split_at(split);
+            let mut m = DequeReader::new(io::empty());
+            assert_eq!(expected, m.push_each(vec![a, b], line));
+        }
+        let mut m = DequeReader::new(io::empty());
+        assert_eq!(expected, m.push_each(input.chunks(1), line));
+    }
+
+    #[test]
+    fn buffer_chunks() {
+        let mut m = DequeReader::new(io::empty());
+        m.push(b"0123456789");
+        let chunks: Vec<&[u8]> = m.buffer_chunks(4).collect();
+        assert_eq!(vec![&b"0123"[..], b"4567", b"89"], chunks);
+    }
+
+    #[test]
+    fn reset() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"old stream".to_vec()));
+            assert_eq!(
+                b"old".to_vec(),
+                m.next_token(|b| b' ' == b).await.unwrap().unwrap()
+            );
+            assert_eq!(b"old stream".len() as u64, m.get_ref().position());
+            m.reset(io::Cursor::new(b"new".to_vec()));
+            assert!(m.buffer().is_empty());
+            assert_eq!(b"new".to_vec(), m.read_to_vec().await.unwrap());
+            assert!(!m.read_more().await.unwrap());
+
+            m.reset(io::Cursor::new(b"again".to_vec()));
+            assert_eq!(b"again".to_vec(), m.read_to_vec().await.unwrap());
+        });
+    }
+
+    /// Only supports vectored reads.
+    struct VectoredOnly(io::Cursor<&'static [u8]>);
+
+    impl AsyncRead for VectoredOnly {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            unreachable!("vectored reads only")
+        }
+
+        fn poll_read_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &mut [IoSliceMut<'_>],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_read_vectored(cx, bufs)
+        }
+    }
+
+    #[test]
+    fn vectored() {
+        task::block_on(async {
+            let mut m = DequeReader::new(VectoredOnly(io::Cursor::new(b"hello")));
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"hello", m.buffer());
+            assert!(!m.read_more().await.unwrap());
+
+            let mut m = DequeReader::new(VectoredOnly(io::Cursor::new(b"bypass")));
+            let mut buf = [0u8; 16];
+            assert_eq!(6, m.read(&mut buf).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn split_buffered() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"GET / HTTP/1.1"),
+                vec![5, 64].into_iter(),
+            ));
+            assert!(m.read_more().await.unwrap());
+            let (prefix, mut inner) = m.split_buffered();
+            assert_eq!(b"GET /", prefix.as_slice());
+            let mut rest = Vec::new();
+            inner.read_to_end(&mut rest).await.unwrap();
+            assert_eq!(b" HTTP/1.1", rest.as_slice());
+
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"GET / HTTP/1.1"),
+                vec![5, 64].into_iter(),
+            ));
+            assert!(m.read_more().await.unwrap());
+            let mut all = Vec::new();
+            m.into_chain().read_to_end(&mut all).await.unwrap();
+            assert_eq!(b"GET / HTTP/1.1", all.as_slice());
+        });
+    }
+
+    #[test]
+    fn prime_on_empty() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut m = DequeReader::new(io::Cursor::new(b"data"));
+        match Pin::new(&mut m).poll_read(&mut cx, &mut []) {
+            Poll::Ready(Ok(0)) => (),
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert!(m.buffer().is_empty());
+
+        m.set_prime_on_empty(true);
+        match Pin::new(&mut m).poll_read(&mut cx, &mut []) {
+            Poll::Ready(Ok(0)) => (),
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert_eq!(b"data", m.buffer());
+
+        let mut m = DequeReader::new(PendingForever);
+        m.set_prime_on_empty(true);
+        assert!(Pin::new(&mut m).poll_read(&mut cx, &mut []).is_pending());
+    }
+
+    #[test]
+    fn leak_check() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"abcdefghijkl"),
+                vec![3; 4].into_iter(),
+            ));
+            m.set_leak_check(4);
+            while m.read_more().await.unwrap() {
+                Pin::new(&mut m).consume(1);
+            }
+            assert_eq!(b"efghijkl", m.buffer());
+        });
+    }
+
+    #[test]
+    fn leak_check_read() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"abcdefghijkl"),
+                vec![3; 4].into_iter(),
+            ));
+            m.set_leak_check(4);
+            let mut buf = [0u8; 1];
+            while m.read_more().await.unwrap() {
+                assert_eq!(1, m.read(&mut buf).await.unwrap());
+            }
+            assert_eq!(b"efghijkl", m.buffer());
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "read more with 6 bytes buffered")]
+    fn leak_check_leaking() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"abcdefghijkl"),
+                vec![3; 4].into_iter(),
+            ));
+            m.set_leak_check(4);
+            while m.read_more().await.unwrap() {}
+        });
+    }
+
+    #[test]
+    fn with_prefix() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b" /1.1")).with_prefix(b"HTTP".to_vec());
+            let mut buf = [0u8; 16];
+            assert_eq!(4, m.read(&mut buf).await.unwrap());
+            assert_eq!(b"HTTP", &buf[..4]);
+            let mut rest = Vec::new();
+            m.read_to_end(&mut rest).await.unwrap();
+            assert_eq!(b" /1.1", rest.as_slice());
+
+            let mut m = DequeReader::new(io::Cursor::new(b"abcdef"))
+                .with_read_sizes(2, 3)
+                .with_prefix(b">".to_vec())
+                .with_window(4)
+                .with_label("composed");
+            assert_eq!(b">", m.buffer());
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b">ab", m.buffer());
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"bcde", m.buffer());
+        });
+    }
+
+    #[test]
+    fn next_token() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"  foo bar  baz"),
+                vec![1, 2, 0, 1, 3, 2, 1, 4].into_iter(),
+            ));
+            let space = |c| c == b' ';
+            assert_eq!(Some(b"foo".to_vec()), m.next_token(space).await.unwrap());
+            assert_eq!(Some(b"bar".to_vec()), m.next_token(space).await.unwrap());
+            assert_eq!(Some(b"baz".to_vec()), m.next_token(space).await.unwrap());
+            assert_eq!(None, m.next_token(space).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn chunk_stream() {
+        task::block_on(async {
+            let m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello world"),
+                vec![2, 0, 6, 3].into_iter(),
+            ));
+            let chunks: Vec<Vec<u8>> = m.into_chunk_stream(4).try_collect().await.unwrap();
+            assert_eq!(
+                vec![&b"he"[..], b"llo ", b"wo", b"rld"],
+                chunks.iter().map(|c| c.as_slice()).collect::<Vec<_>>()
+            );
+            assert_eq!(b"hello world", chunks.concat().as_slice());
+        });
+    }
+
+    #[test]
+    fn read_cstr() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"hello\0\0world\0"));
+            assert_eq!(Some(b"hello".to_vec()), m.read_cstr().await.unwrap());
+            assert_eq!(Some(b"".to_vec()), m.read_cstr().await.unwrap());
+            assert_eq!(Some(b"world".to_vec()), m.read_cstr().await.unwrap());
+            assert_eq!(None, m.read_cstr().await.unwrap());
+
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"ab\0cd"),
+                vec![1, 3, 1].into_iter(),
+            ));
+            assert_eq!(Some(b"ab".to_vec()), m.read_cstr().await.unwrap());
+            let err = m.read_cstr().await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        });
+    }
+
+    #[test]
+    fn read_ints() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(
+                    b"\x01\x02\x03\x02\x01\x01\x02\x03\x04\x04\x03\x02\x01\
+                      \x01\x02\x03\x04\x05\x06\x07\x08\x08\x07\x06\x05\x04\x03\x02\x01\x09",
+                ),
+                vec![1, 2, 3, 4, 5, 6, 7, 8, 9].into_iter(),
+            ));
+            assert_eq!(0x01, m.read_u8().await.unwrap());
+            assert_eq!(0x0203, m.read_u16_be().await.unwrap());
+            assert_eq!(0x0102, m.read_u16_le().await.unwrap());
+            assert_eq!(0x0102_0304, m.read_u32_be().await.unwrap());
+            assert_eq!(0x0102_0304, m.read_u32_le().await.unwrap());
+            assert_eq!(0x0102_0304_0506_0708, m.read_u64_be().await.unwrap());
+            assert_eq!(0x0102_0304_0506_0708, m.read_u64_le().await.unwrap());
+
+            // one byte left
+            for err in &[
+                m.read_u16_be().await.unwrap_err(),
+                m.read_u16_le().await.unwrap_err(),
+                m.read_u32_be().await.unwrap_err(),
+                m.read_u32_le().await.unwrap_err(),
+                m.read_u64_be().await.unwrap_err(),
+                m.read_u64_le().await.unwrap_err(),
+            ] {
+                assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            }
+            assert_eq!(0x09, m.read_u8().await.unwrap());
+            let err = m.read_u8().await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        });
+    }
+
+    #[test]
+    fn window() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"abcdefghijklmnopqrst"),
+                vec![3, 7, 1, 2, 7].into_iter(),
+            ))
+            .with_window(5);
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"abc", m.buffer());
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"fghij", m.buffer());
+            while m.read_more().await.unwrap() {
+                assert!(m.buffer().len() <= 5);
+            }
+            assert_eq!(b"pqrst", m.buffer());
+        });
+    }
+
+    fn checked_frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(&crate::crc32(payload).to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn checked_frame_valid() {
+        task::block_on(async {
+            let mut data = checked_frame(b"hello");
+            data.extend(checked_frame(b""));
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(data),
+                vec![3, 0, 5, 2, 3, 5, 3].into_iter(),
+            ));
+            assert_eq!(
+                Some(b"hello".to_vec()),
+                m.read_checked_frame().await.unwrap()
+            );
+            assert_eq!(Some(b"".to_vec()), m.read_checked_frame().await.unwrap());
+            assert_eq!(None, m.read_checked_frame().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn checked_frame_corrupt() {
+        task::block_on(async {
+            let mut data = checked_frame(b"hello");
+            data[5] ^= 0x20;
+            data.extend(checked_frame(b"world"));
+            let mut m = DequeReader::new(io::Cursor::new(data));
+            let err = m.read_checked_frame().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert_eq!(
+                Some(b"world".to_vec()),
+                m.read_checked_frame().await.unwrap()
+            );
+
+            let mut m = DequeReader::new(io::Cursor::new(checked_frame(b"hello")));
+            let always_zero = |_: &[u8]| 0;
+            let err = m.read_checked_frame_with(always_zero).await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        });
+    }
+
+    #[test]
+    fn checked_frame_too_long() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(checked_frame(b"hello")));
+            m.set_max_frame_len(4);
+            let err = m.read_checked_frame().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+            m.set_max_frame_len(5);
+            assert_eq!(
+                Some(b"hello".to_vec()),
+                m.read_checked_frame().await.unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn checked_frame_truncated() {
+        task::block_on(async {
+            let data = checked_frame(b"hello");
+            for len in &[2, 6, data.len() - 1] {
+                let mut m = DequeReader::new(io::Cursor::new(data[..*len].to_vec()));
+                let err = m.read_checked_frame().await.unwrap_err();
+                assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            }
+        });
+    }
+
+    #[test]
+    fn framed_with() {
+        task::block_on(async {
+            // a type byte, and a one byte length
+            let parse = |header: &[u8]| (header[0], usize::from(header[1]));
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"\x07\x03abc\x08\x05de"),
+                vec![1, 0, 3, 2, 1, 9].into_iter(),
+            ));
+            assert_eq!(
+                (7, b"abc".to_vec()),
+                m.read_framed_with(2, parse).await.unwrap()
+            );
+            let err = m.read_framed_with(2, parse).await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert_eq!(b"\x08\x05de", m.buffer());
+
+            let mut m = DequeReader::new(io::Cursor::new(b"\x07\xff"));
+            let huge = |_: &[u8]| ((), usize::MAX);
+            let err = m.read_framed_with(2, huge).await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            m.set_max_frame_len(usize::MAX);
+            let err = m.read_framed_with(2, huge).await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert_eq!(b"\x07\xff", m.buffer());
+        });
+    }
+
+    #[test]
+    fn expect_magic() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"\x89PNG\r\nrest"),
+                vec![2, 0, 3, 9].into_iter(),
+            ));
+            m.expect_magic(b"\x89PNG").await.unwrap();
+            let err = m.expect_magic(b"\r\n\x1a").await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            m.expect_magic(b"\r\n").await.unwrap();
+            assert_eq!(b"rest".to_vec(), m.read_to_vec().await.unwrap());
+
+            let mut m = DequeReader::new(io::Cursor::new(b"GIF8"));
+            let err = m.expect_magic(b"GIF89a").await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        });
+    }
+
+    #[test]
+    fn find() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"name,value"),
+                vec![2, 0, 1, 3, 9].into_iter(),
+            ));
+            assert_eq!(Some(4), m.find(b',').await.unwrap());
+            assert_eq!(Some(4), m.find(b',').await.unwrap());
+            assert_eq!(b"name,", &m.buffer()[..5]);
+            assert_eq!(None, m.find(b';').await.unwrap());
+            assert_eq!(b"name,value", m.buffer());
+        });
+    }
+
+    #[test]
+    fn read_more_capped() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"abcdefgh"));
+            assert_eq!(3, m.read_more_capped(3).await.unwrap());
+            assert_eq!(b"abc", m.buffer());
+
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"abcdefgh"),
+                vec![1, 2, 0, 5].into_iter(),
+            ));
+            // stops when the inner reader returns `Pending`
+            assert_eq!(3, m.read_more_capped(10).await.unwrap());
+            assert!(!m.would_block());
+            assert_eq!(5, m.read_more_capped(10).await.unwrap());
+            assert_eq!(0, m.read_more_capped(10).await.unwrap());
+            assert_eq!(b"abcdefgh", m.buffer());
+        });
+    }
+
+    #[test]
+    fn events() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"abcdefgh"),
+                vec![3, 0, 4, 9].into_iter(),
+            ));
+            m.push(b"xy");
+            let events: Vec<FillEvent> = m.events().try_collect().await.unwrap();
+            assert_eq!(
+                vec![
+                    FillEvent::Data(2),
+                    FillEvent::Data(3),
+                    FillEvent::Data(4),
+                    FillEvent::Data(1),
+                    FillEvent::Eof
+                ],
+                events
+            );
+        });
+    }
+
+    #[test]
+    fn read_more_into() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"abcdefgh"),
+                vec![2, 3, 0, 9].into_iter(),
+            ));
+            assert!(m.read_more().await.unwrap());
+            let mut dst = SliceDeque::new();
+            dst.extend_from_slice(b">");
+            assert_eq!(2, m.read_more_into(&mut dst).await.unwrap());
+            assert_eq!(3, m.read_more_into(&mut dst).await.unwrap());
+            assert!(m.buffer().is_empty());
+            assert_eq!(3, m.read_more_into(&mut dst).await.unwrap());
+            assert_eq!(0, m.read_more_into(&mut dst).await.unwrap());
+            assert_eq!(b">abcdefgh", dst.as_slice());
+
+            // larger than fits on the stack
+            let mut m =
+                DequeReader::new(io::Cursor::new(vec![7u8; 6000])).with_read_sizes(5000, 5000);
+            let mut dst = SliceDeque::new();
+            assert_eq!(5000, m.read_more_into(&mut dst).await.unwrap());
+            assert_eq!(1000, m.read_more_into(&mut dst).await.unwrap());
+            assert_eq!(vec![7u8; 6000], dst.to_vec());
+        });
+    }
+
+    #[test]
+    fn consume_through_seq() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"GET / HTTP/1.1\r\nHost: a\r\n\r\nbody\r\n"),
+                [3, 14, 0, 9, 1, 1, 2, 64].iter().copied(),
+            ));
+            assert_eq!(
+                b"GET / HTTP/1.1\r\nHost: a\r\n\r\n".to_vec(),
+                m.consume_through_seq(b"\r\n\r\n").await.unwrap().unwrap()
+            );
+            assert_eq!(None, m.consume_through_seq(b"\r\n\r\n").await.unwrap());
+            assert_eq!(b"body\r\n", m.buffer());
+        });
+    }
+
+    #[test]
+    fn wait_for() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut m = DequeReader::new(ShortRead::new(
+            io::Cursor::new(b"abcdefgh"),
+            vec![2, 0, 2, 0, 2, 0, 2].into_iter(),
+        ));
+        {
+            let mut watermark = Box::pin(m.wait_for(5));
+            assert!(watermark.as_mut().poll(&mut cx).is_pending());
+            assert!(watermark.as_mut().poll(&mut cx).is_pending());
+            match watermark.as_mut().poll(&mut cx) {
+                Poll::Ready(Ok(())) => (),
+                other => panic!("unexpected: {:?}", other),
+            }
+        }
+        assert_eq!(b"abcdef", m.buffer());
+
+        task::block_on(async {
+            let err = m.wait_for(9).await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        });
+    }
+
+    #[test]
+    fn expect() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"key: value\r\nnext"),
+                vec![3, 0, 1, 2, 8, 9].into_iter(),
+            ));
+            m.expect(b"key:").await.unwrap();
+            m.expect(b" ").await.unwrap();
+            m.expect(b"value").await.unwrap();
+            let err = m.expect(b"\r\n\r\n").await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert!(err.to_string().contains("at byte 2"), "{}", err);
+            m.expect(b"\r\n").await.unwrap();
+
+            let err = m.expect(b"next line").await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert_eq!(b"next", m.buffer());
+        });
+    }
+
+    #[test]
+    fn matches() {
+        task::block_on(async {
+            let m = DequeReader::new(io::Cursor::new(b"aaaa"));
+            let found: Vec<u64> = m.matches(b"aa".to_vec()).try_collect().await.unwrap();
+            assert_eq!(vec![0, 1, 2], found);
+
+            let m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"ERROR one; ok; ERROR two; ERR"),
+                vec![3, 0, 4, 1, 9, 2, 64].into_iter(),
+            ));
+            let found: Vec<u64> = m.matches(b"ERROR".to_vec()).try_collect().await.unwrap();
+            assert_eq!(vec![0, 15], found);
+        });
+    }
+
+    #[test]
+    fn limited() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"\x05helloworld"),
+                vec![3, 0, 1, 64].into_iter(),
+            ));
+            let len = m.read_u8().await.unwrap();
+            let mut sub = m.limited(u64::from(len));
+            let mut region = Vec::new();
+            sub.read_to_end(&mut region).await.unwrap();
+            assert_eq!(0, sub.remaining());
+            assert_eq!(b"hello", region.as_slice());
+            assert_eq!(b"world".to_vec(), m.read_to_vec().await.unwrap());
+
+            let mut m = DequeReader::new(io::Cursor::new(b"one\ntwo\nthree"));
+            let mut lines = Vec::new();
+            m.limited(8).read_to_end(&mut lines).await.unwrap();
+            let mut sub = m.limited(3);
+            let mut line = String::new();
+            sub.read_line(&mut line).await.unwrap();
+            assert_eq!("thr", line);
+            assert_eq!(b"ee".to_vec(), m.read_to_vec().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn high_water_mark() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(vec![7u8; 10_000]),
+                vec![100, 3000, 4000, 10, 4096].into_iter(),
+            ));
+            assert!(m.read_more().await.unwrap());
+            Pin::new(&mut m).consume(50);
+            assert!(m.read_more().await.unwrap());
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(7050, m.high_water_mark());
+            Pin::new(&mut m).consume(7000);
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(60, m.buffer().len());
+            assert_eq!(7050, m.high_water_mark());
+        });
+    }
+
+    #[test]
+    fn label() {
+        task::block_on(async {
+            let mut m = DequeReader::new(PendingForever)
+                .with_read_timeout(Duration::from_millis(1))
+                .with_label("upstream");
+            let err = m.read_more().await.unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+            assert!(err.to_string().starts_with("upstream: "), "{}", err);
+
+            let mut m = DequeReader::new(io::Cursor::new(b"a")).with_label("body");
+            let err = m.read_u16_be().await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert!(err.to_string().contains("body"), "{}", err);
+            let debug = format!("{:?}", m);
+            assert!(debug.contains("label: Some(\"body\")"), "{}", debug);
+            assert!(debug.contains("buffered: 1"), "{}", debug);
+
+            let mut m = DequeReader::new(io::Cursor::new(b"\0\0\x01\0")).with_label("frames");
+            m.set_max_frame_len(255);
+            let err = m.read_checked_frame().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert_eq!("frames: frame too long", err.to_string());
+        });
+    }
+
+    #[test]
+    fn fill_target() {
+        task::block_on(async {
+            let chunks = || vec![2, 3, 4, 0, 2].into_iter();
+
+            let mut m = DequeReader::new(ShortRead::new(io::Cursor::new(b"hello world"), chunks()));
+            assert_eq!(b"he", m.fill_buf().await.unwrap());
+
+            let mut m = DequeReader::new(ShortRead::new(io::Cursor::new(b"hello world"), chunks()));
+            m.set_fill_target(8);
+            assert_eq!(b"hello wor", m.fill_buf().await.unwrap());
+            Pin::new(&mut m).consume(9);
+
+            // stops at the Pending, then at the eof
+            m.set_fill_target(100);
+            assert_eq!(b"ld", m.fill_buf().await.unwrap());
+            Pin::new(&mut m).consume(2);
+            assert_eq!(b"", m.fill_buf().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn shutdown() {
+        task::block_on(async {
+            let mut m = DequeReader::new(Vec::new());
+            m.write_all(b"hello").await.unwrap();
+            m.shutdown().await.unwrap();
+            let err = m.write_all(b"world").await.unwrap_err();
+            assert_eq!(io::ErrorKind::BrokenPipe, err.kind());
+            assert_eq!(b"hello", m.into_inner().as_slice());
+        });
+    }
+
+    #[test]
+    fn fill_at_least_timeout() {
+        task::block_on(async {
+            let timeout = Duration::from_millis(30);
+            let mut m = DequeReader::new(PendingForever);
+            let start = Instant::now();
+            let err = m.fill_at_least_timeout(1, timeout).await.unwrap_err();
+            assert_eq!(io::ErrorKind::TimedOut, err.kind());
+            assert!(start.elapsed() >= timeout);
+
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello world"),
+                vec![2, 0, 2, 3, 4].into_iter(),
+            ));
+            let found = m.fill_at_least_timeout(4, timeout).await.unwrap();
+            assert_eq!(b"hell", found);
+            let found = m.fill_at_least_timeout(20, timeout).await.unwrap();
+            assert_eq!(b"hello world", found);
+        });
+    }
+
+    #[test]
+    fn advance_line() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello\n\nworld\nbye"),
+                vec![3, 4, 0, 1, 2, 8].into_iter(),
+            ));
+            let expected: [&[u8]; 4] = [b"hello", b"", b"world", b"bye"];
+            let mut seen = 0;
+            while let Some(line) = m.advance_line().await.unwrap() {
+                assert_eq!(expected[seen], line);
+                seen += 1;
+            }
+            assert_eq!(expected.len(), seen);
+            assert_eq!(b"", m.buffer());
+            assert_eq!(None, m.advance_line().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn would_block() {
+        let flag = Arc::new(Flag::default());
+        let waker = futures::task::waker(flag);
+        let mut cx = Context::from_waker(&waker);
+
+        let mut m = DequeReader::new(ShortRead::new(
+            io::Cursor::new(b"hello"),
+            vec![0, 2, 0].into_iter(),
+        ));
+        assert!(!m.would_block());
+        assert!(Pin::new(&mut m).poll_read_more(&mut cx).is_pending());
+        assert!(m.would_block());
+        assert!(Pin::new(&mut m).poll_read_more(&mut cx).is_ready());
+        assert!(!m.would_block());
+        assert_eq!(b"he", m.buffer());
+
+        assert!(Pin::new(&mut m).poll_read_more(&mut cx).is_pending());
+        assert!(m.would_block());
+        m.push(b"y");
+        assert!(!m.would_block());
+    }
+
+    #[test]
+    fn read_to_vec() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello world"),
+                vec![2, 0, 3, 6].into_iter(),
+            ));
+            assert!(m.read_more().await.unwrap());
+            Pin::new(&mut m).consume(1);
+            assert_eq!(b"ello world", m.read_to_vec().await.unwrap().as_slice());
+            assert_eq!(b"", m.buffer());
+            assert!(m.read_to_vec().await.unwrap().is_empty());
+        });
+    }
+
+    pin_project_lite::pin_project! {
+        /// A reader which must stay pinned.
+        struct NotUnpin<R> {
+            #[pin]
+            inner: R,
+            #[pin]
+            _pinned: PhantomPinned,
+        }
+    }
+
+    impl<R: AsyncRead> AsyncRead for NotUnpin<R> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            self.project().inner.poll_read(cx, buf)
+        }
+    }
+
+    #[test]
+    fn poll_peek() {
+        let flag = Arc::new(Flag::default());
+        let waker = futures::task::waker(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let m = DequeReader::new(NotUnpin {
+            inner: ShortRead::new(io::Cursor::new(b"hello"), vec![2, 0, 2, 1].into_iter()),
+            _pinned: PhantomPinned,
+        });
+        futures::pin_mut!(m);
+
+        assert!(m.as_mut().poll_peek(&mut cx, 3).is_pending());
+        assert!(flag.woken());
+        match m.as_mut().poll_peek(&mut cx, 3) {
+            Poll::Ready(Ok(found)) => assert_eq!(b"hel", found),
+            other => panic!("unexpected: {:?}", other),
+        }
+        match m.as_mut().poll_peek(&mut cx, 10) {
+            Poll::Ready(Ok(found)) => assert_eq!(b"hello", found),
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert_eq!(b"hello", m.buffer());
+    }
+
+    #[test]
+    fn read_sizes() {
+        task::block_on(async {
+            let data = vec![7u8; 10_000];
+            let mut m = DequeReader::new(io::Cursor::new(data)).with_read_sizes(10, 5000);
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(10, m.buffer().len());
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(5010, m.buffer().len());
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(10_000, m.buffer().len());
+            assert!(!m.read_more().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn try_parse() {
+        // a length byte, followed by that many bytes
+        fn token(buf: &[u8]) -> Option<(usize, Vec<u8>)> {
+            let len = usize::from(*buf.first()?);
+            buf.get(1..=len).map(|token| (len + 1, token.to_vec()))
+        }
+
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"\x03abc\x05hello\x04ab"),
+                vec![2, 0, 3, 1, 4, 6].into_iter(),
+            ));
+            assert_eq!(Some(b"abc".to_vec()), m.try_parse(token).await.unwrap());
+            assert_eq!(Some(b"hello".to_vec()), m.try_parse(token).await.unwrap());
+            assert_eq!(None, m.try_parse(token).await.unwrap());
+            assert_eq!(b"\x04ab", m.buffer());
+        });
+    }
+
+    #[test]
+    fn json_values() {
+        task::block_on(async {
+            let input: &[u8] =
+                b" {\"a\": [1, {\"b\": \"}]\"}], \"c\": \"\\\"{\"}\n[[]]\"x\\\\\" 12\t-3.5e2 ";
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(input),
+                vec![3, 0, 5, 1, 1, 2, 7, 4, 1, 2, 3, 5, 8, 13, 21].into_iter(),
+            ));
+            for expected in &[
+                &b"{\"a\": [1, {\"b\": \"}]\"}], \"c\": \"\\\"{\"}"[..],
+                b"[[]]",
+                b"\"x\\\\\"",
+                b"12",
+                b"-3.5e2",
+            ] {
+                assert_eq!(
+                    Some(expected.to_vec()),
+                    m.read_json_value().await.unwrap(),
+                    "{:?}",
+                    String::from_utf8_lossy(expected)
+                );
+            }
+            assert_eq!(None, m.read_json_value().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn json_sequence() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"[1],[2] , 3,\"4\""),
+                vec![2, 0, 3, 1, 64].into_iter(),
+            ));
+            for expected in &[&b"[1]"[..], b"[2]", b"3", b"\"4\""] {
+                assert_eq!(Some(expected.to_vec()), m.read_json_value().await.unwrap());
+            }
+            assert_eq!(None, m.read_json_value().await.unwrap());
+
+            let mut m = DequeReader::new(io::Cursor::new(b": 1"));
+            let err = m.read_json_value().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        });
+    }
+
+    #[test]
+    fn json_truncated() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"[{\"a\": \"]}"));
+            let err = m.read_json_value().await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+
+            let mut m = DequeReader::new(io::Cursor::new(b" ]"));
+            let err = m.read_json_value().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        });
+    }
+
+    #[test]
+    fn consume_while() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"    token  "),
+                vec![2, 0, 1, 3, 5].into_iter(),
+            ));
+            assert_eq!(4, m.consume_while(|b| b' ' == b).await.unwrap());
+            assert_eq!(b"to", m.buffer());
+            assert_eq!(0, m.consume_while(|b| b' ' == b).await.unwrap());
+            assert_eq!(
+                5,
+                m.consume_while(|b| b.is_ascii_alphabetic()).await.unwrap()
+            );
+            assert_eq!(2, m.consume_while(|b| b' ' == b).await.unwrap());
+            assert!(m.buffer().is_empty());
+        });
+    }
+
+    #[test]
+    fn slack() {
+        task::block_on(async {
+            let mut m = DequeReader::with_capacity(io::Cursor::new(vec![7u8; 100]), 4096);
+            let empty = m.slack();
+            assert!(empty >= 4096);
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(empty - 100, m.slack());
+            Pin::new(&mut m).consume(60);
+            assert_eq!(empty - 40, m.slack());
+        });
+    }
+
+    #[test]
+    fn writable() {
+        task::block_on(async {
+            let mut m = DequeReader::new(StallWrite::new(
+                io::Cursor::new(Vec::new()),
+                // two stalls, then the probe, then a short write
+                vec![0, 0, 1, 5].into_iter(),
+                std::iter::empty(),
+                std::iter::empty(),
+            ));
+            m.writable().await.unwrap();
+            assert_eq!(2, m.get_ref().stalls());
+            assert_eq!(5, m.write(b"hello world").await.unwrap());
+            assert_eq!(b"hello", m.get_ref().get_ref().get_ref().as_slice());
+        });
+    }
+
+    #[test]
+    fn consume_back() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"hello"));
+            assert!(m.read_more().await.unwrap());
+            m.consume_back(2);
+            assert_eq!(b"hel", m.buffer());
+            m.consume_back(3);
+            assert!(m.buffer().is_empty());
+        });
+    }
+
+    #[test]
+    fn replace_inner() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"hello wo".to_vec()));
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(
+                Some(b"hello".to_vec()),
+                m.next_token(|b| b' ' == b).await.unwrap()
+            );
+
+            let old = m.replace_inner(io::Cursor::new(b"rld again".to_vec()));
+            assert_eq!(8, old.position());
+            assert_eq!(
+                Some(b"world".to_vec()),
+                m.next_token(|b| b' ' == b).await.unwrap()
+            );
+            assert_eq!(
+                Some(b"again".to_vec()),
+                m.next_token(|b| b' ' == b).await.unwrap()
+            );
+            assert_eq!(None, m.next_token(|b| b' ' == b).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn read_chunk() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello world"),
+                vec![3, 0, 1, 5, 9].into_iter(),
+            ));
+            let mut sizes = Vec::new();
+            loop {
+                let n = m.read_chunk().await.unwrap();
+                sizes.push(n);
+                if 0 == n {
+                    break;
+                }
+            }
+            assert_eq!(vec![3, 1, 5, 2, 0], sizes);
+            assert_eq!(b"hello world", m.buffer());
+        });
+    }
+
+    #[test]
+    fn budget() {
+        task::block_on(async {
+            let budget = MemoryBudget::new(8);
+            let mut a =
+                DequeReader::new(io::Cursor::new(b"aaaaaaaaaa")).with_budget(budget.clone());
+            let mut b = DequeReader::new(io::Cursor::new(b"bbbb")).with_budget(budget.clone());
+
+            assert!(a.read_more().await.unwrap());
+            assert_eq!(b"aaaaaaaa", a.buffer());
+            assert_eq!(0, budget.available());
+            let flag = Arc::new(Flag::default());
+            let waker = futures::task::waker(flag.clone());
+            let mut cx = Context::from_waker(&waker);
+            assert!(Pin::new(&mut b).poll_read_more(&mut cx).is_pending());
+
+            Pin::new(&mut a).consume(3);
+            assert!(flag.woken());
+            assert_eq!(3, budget.available());
+            assert!(b.read_more().await.unwrap());
+            assert_eq!(b"bbb", b.buffer());
+            assert_eq!(8, budget.used());
+
+            drop(b);
+            assert_eq!(5, budget.used());
+            assert_eq!(b"aaaaaaa", a.read_to_vec().await.unwrap().as_slice());
+            assert_eq!(0, budget.used());
+        });
+    }
+
+    #[test]
+    fn buffer_regions() {
+        task::block_on(async {
+            let mut m = DequeReader::new(io::Cursor::new(b"hello world"));
+            assert!(m.read_more().await.unwrap());
+            Pin::new(&mut m).consume(3);
+            let (first, second) = m.buffer_regions();
+            assert_eq!(m.buffer(), [first, second].concat().as_slice());
+        });
+    }
+
+    #[test]
+    fn inner_reads() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello world"),
+                vec![3, 4, 4].into_iter(),
+            ));
+            let mut productive = 0;
+            while m.read_more().await.unwrap() {
+                productive += 1;
+            }
+            assert_eq!(3, productive);
+            assert_eq!(productive + 1, m.inner_reads());
+
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(vec![0u8; 5000]),
+                vec![0, 5000].into_iter(),
+            ));
+            let mut buf = [0u8; 8192];
+            assert_eq!(5000, m.read(&mut buf).await.unwrap());
+            assert_eq!(2, m.inner_reads());
+        });
+    }
+
+    #[test]
+    fn peek_line() {
+        task::block_on(async {
+            let mut m = DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello\nworld"),
+                vec![2, 0, 3, 4, 9].into_iter(),
+            ));
+            assert_eq!(Some(&b"hello\n"[..]), m.peek_line().await.unwrap());
+            assert_eq!(Some(&b"hello\n"[..]), m.peek_line().await.unwrap());
+            Pin::new(&mut m).consume(6);
+            assert_eq!(Some(&b"world"[..]), m.peek_line().await.unwrap());
+            Pin::new(&mut m).consume(5);
+            assert_eq!(None, m.peek_line().await.unwrap());
         });
     }
 }
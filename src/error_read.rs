@@ -0,0 +1,115 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::label;
+
+pin_project! {
+    /// Return an error after a fixed number of bytes have been read, to test error handling.
+    ///
+    /// Reads are shortened so they stop exactly at `after` bytes; every read after that
+    /// fails with an error of the given kind.
+    ///
+    /// ```rust
+    /// use futures::io;
+    /// use futures::io::AsyncReadExt as _;
+    /// # async_std::task::block_on(async {
+    /// let mut r = aiowrap::ErrorRead::new(
+    ///     io::Cursor::new(b"hello"),
+    ///     2,
+    ///     io::ErrorKind::ConnectionReset,
+    /// );
+    /// let mut buf = [0u8; 5];
+    /// assert_eq!(2, r.read(&mut buf).await.unwrap());
+    /// let err = r.read(&mut buf).await.unwrap_err();
+    /// assert_eq!(io::ErrorKind::ConnectionReset, err.kind());
+    /// # });
+    /// ```
+    pub struct ErrorRead<R> {
+        #[pin]
+        inner: R,
+        remaining: usize,
+        kind: io::ErrorKind,
+        label: Option<&'static str>,
+    }
+}
+
+impl<R> ErrorRead<R> {
+    pub fn new(inner: R, after: usize, kind: io::ErrorKind) -> Self {
+        ErrorRead {
+            inner,
+            remaining: after,
+            kind,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// generated by this reader.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for ErrorRead<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorRead")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for ErrorRead<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if 0 == *this.remaining {
+            return Poll::Ready(Err(label::error(*this.label, *this.kind, "injected error")));
+        }
+        let wanted = buf.len().min(*this.remaining);
+        let found = ready!(this.inner.poll_read(cx, &mut buf[..wanted]))?;
+        *this.remaining -= found;
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::ErrorRead;
+
+    #[test]
+    fn errors_after() {
+        task::block_on(async {
+            let mut r = ErrorRead::new(io::Cursor::new(b"hello"), 3, io::ErrorKind::Other)
+                .with_label("upload");
+            let mut out = Vec::new();
+            let err = r.read_to_end(&mut out).await.unwrap_err();
+            assert_eq!(io::ErrorKind::Other, err.kind());
+            assert_eq!("upload: injected error", err.to_string());
+            assert_eq!(b"hel", out.as_slice());
+
+            let mut buf = [0u8; 1];
+            assert!(r.read(&mut buf).await.is_err());
+        });
+    }
+}
@@ -0,0 +1,128 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Read from a primary source until it fails, then transparently continue
+    /// from a secondary source, which carries the same stream.
+    ///
+    /// On failover, the bytes already delivered from the primary are read from
+    /// the secondary and discarded, so reading continues from the point of failure.
+    /// If the secondary ends before that point, the read fails with `UnexpectedEof`.
+    ///
+    /// The end of the primary stream is not a failure, and does not fail over.
+    pub struct Failover<A, B> {
+        #[pin]
+        primary: A,
+        #[pin]
+        secondary: B,
+        error: Option<io::Error>,
+        offset: u64,
+    }
+}
+
+impl<A, B> Failover<A, B> {
+    pub fn new(primary: A, secondary: B) -> Self {
+        Failover {
+            primary,
+            secondary,
+            error: None,
+            offset: 0,
+        }
+    }
+
+    /// The error from the primary which caused the failover, if it has happened.
+    pub fn primary_error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl<A: AsyncRead, B: AsyncRead> AsyncRead for Failover<A, B> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut this = self.project();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        if this.error.is_none() {
+            match ready!(this.primary.poll_read(cx, buf)) {
+                Ok(found) => {
+                    *this.offset += found as u64;
+                    return Poll::Ready(Ok(found));
+                }
+                Err(e) => *this.error = Some(e),
+            }
+        }
+
+        // skip what the primary already delivered, using the caller's buffer as scratch
+        while *this.offset > 0 {
+            let wanted = (buf.len() as u64).min(*this.offset) as usize;
+            let found = ready!(this.secondary.as_mut().poll_read(cx, &mut buf[..wanted]))?;
+            if 0 == found {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "secondary ended before the failure point",
+                )));
+            }
+            *this.offset -= found as u64;
+        }
+
+        this.secondary.poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::ErrorRead;
+    use crate::Failover;
+    use crate::ShortRead;
+
+    #[test]
+    fn fails_over() {
+        task::block_on(async {
+            let data = b"hello world";
+            let mut r = Failover::new(
+                ErrorRead::new(io::Cursor::new(data), 3, io::ErrorKind::ConnectionReset),
+                ShortRead::new(io::Cursor::new(data), vec![2, 2, 4, 4].into_iter()),
+            );
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).await.unwrap();
+            assert_eq!(data, out.as_slice());
+            assert_eq!(
+                io::ErrorKind::ConnectionReset,
+                r.primary_error().unwrap().kind()
+            );
+        });
+    }
+
+    #[test]
+    fn secondary_short() {
+        task::block_on(async {
+            let mut r = Failover::new(
+                ErrorRead::new(io::Cursor::new(b"hello"), 3, io::ErrorKind::Other),
+                io::Cursor::new(b"he"),
+            );
+            let mut out = Vec::new();
+            let err = r.read_to_end(&mut out).await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert_eq!(b"hel", out.as_slice());
+        });
+    }
+}
@@ -0,0 +1,98 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Split each of the inner reader's reads into fragments of at most `mtu` bytes,
+    /// returning one fragment per read.
+    ///
+    /// Unlike `ShortRead`, nothing is lost, or left unread in the inner reader: the rest
+    /// of each inner read is held here, and returned by the following reads.
+    pub struct Fragment<R> {
+        #[pin]
+        inner: R,
+        mtu: usize,
+        pending: Vec<u8>,
+        pos: usize,
+    }
+}
+
+impl<R> Fragment<R> {
+    /// # Panics
+    ///
+    /// Panics if `mtu` is zero.
+    pub fn new(inner: R, mtu: usize) -> Self {
+        assert!(mtu > 0, "mtu must be non-zero");
+        Fragment {
+            inner,
+            mtu,
+            pending: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Note that any held fragments are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Fragment<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let wanted = buf.len().min(*this.mtu);
+
+        if *this.pos < this.pending.len() {
+            let using = wanted.min(this.pending.len() - *this.pos);
+            buf[..using].copy_from_slice(&this.pending[*this.pos..*this.pos + using]);
+            *this.pos += using;
+            return Poll::Ready(Ok(using));
+        }
+
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        let using = found.min(wanted);
+        this.pending.clear();
+        this.pending.extend_from_slice(&buf[using..found]);
+        *this.pos = 0;
+        Poll::Ready(Ok(using))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::Fragment;
+
+    #[test]
+    fn fragments() {
+        task::block_on(async {
+            let data: Vec<u8> = (0..10u8).collect();
+            let mut r = Fragment::new(io::Cursor::new(data.clone()), 4);
+            let mut buf = [0u8; 64];
+            let mut out = Vec::new();
+            let mut sizes = Vec::new();
+            loop {
+                let n = r.read(&mut buf).await.unwrap();
+                if 0 == n {
+                    break;
+                }
+                sizes.push(n);
+                out.extend_from_slice(&buf[..n]);
+            }
+            assert_eq!(vec![4, 4, 2], sizes);
+            assert_eq!(data, out);
+        });
+    }
+}
@@ -0,0 +1,146 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use futures::AsyncWrite;
+use pin_project_lite::pin_project;
+
+fn broken_pipe<T>() -> Poll<io::Result<T>> {
+    Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+}
+
+pin_project! {
+    /// Simulate a half-open connection: reads are passed through, but writes fail
+    /// with `BrokenPipe`, as if the peer had stopped receiving. Flushes and closes
+    /// are passed through.
+    ///
+    /// See `WriteOnly` for the inverse.
+    pub struct HalfOpen<T> {
+        #[pin]
+        inner: T,
+    }
+}
+
+impl<T> HalfOpen<T> {
+    pub fn new(inner: T) -> Self {
+        HalfOpen { inner }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for HalfOpen<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for HalfOpen<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        broken_pipe()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+pin_project! {
+    /// The inverse of `HalfOpen`: writes are passed through, but reads fail with `BrokenPipe`.
+    pub struct WriteOnly<T> {
+        #[pin]
+        inner: T,
+    }
+}
+
+impl<T> WriteOnly<T> {
+    pub fn new(inner: T) -> Self {
+        WriteOnly { inner }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for WriteOnly<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        broken_pipe()
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for WriteOnly<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+    use futures::io::AsyncWriteExt as _;
+
+    use crate::HalfOpen;
+    use crate::WriteOnly;
+
+    #[test]
+    fn half_open() {
+        task::block_on(async {
+            let mut s = HalfOpen::new(io::Cursor::new(b"hello".to_vec()));
+            let mut buf = [0u8; 5];
+            s.read_exact(&mut buf).await.unwrap();
+            assert_eq!(b"hello", &buf);
+
+            let err = s.write_all(b"reply").await.unwrap_err();
+            assert_eq!(io::ErrorKind::BrokenPipe, err.kind());
+            s.flush().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn write_only() {
+        task::block_on(async {
+            let mut s = WriteOnly::new(io::Cursor::new(Vec::new()));
+            s.write_all(b"hello").await.unwrap();
+
+            let mut buf = [0u8; 5];
+            let err = s.read(&mut buf).await.unwrap_err();
+            assert_eq!(io::ErrorKind::BrokenPipe, err.kind());
+            assert_eq!(b"hello", s.into_inner().into_inner().as_slice());
+        });
+    }
+}
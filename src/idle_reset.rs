@@ -0,0 +1,222 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::label;
+use crate::timer::Deadline;
+use crate::Timer;
+
+pin_project! {
+    /// Fail with `ConnectionReset` if the reader goes idle for longer than `timeout`,
+    /// modelling servers which drop idle connections.
+    ///
+    /// The reader is idle from when it is first polled, and from each successful read,
+    /// until the next successful read, whether or not it is being polled in between.
+    /// Once reset, every read fails.
+    pub struct IdleReset<R> {
+        #[pin]
+        inner: R,
+        timeout: Duration,
+        // When the reader was first polled, or last read successfully.
+        last: Option<Instant>,
+        // The sleep for the idle time remaining, and the `last` it was armed for.
+        deadline: Option<(Instant, Deadline)>,
+        timer: Option<Arc<dyn Timer>>,
+        reset: bool,
+        label: Option<&'static str>,
+    }
+}
+
+impl<R> IdleReset<R> {
+    pub fn new(inner: R, timeout: Duration) -> Self {
+        IdleReset {
+            inner,
+            timeout,
+            last: None,
+            deadline: None,
+            timer: None,
+            reset: false,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// generated by this reader.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Use this `Timer` for the timeout, instead of the default `ThreadTimer`.
+    pub fn set_timer(&mut self, timer: Arc<dyn Timer>) {
+        self.timer = Some(timer);
+        self.deadline = None;
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for IdleReset<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdleReset")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("timeout", &self.timeout)
+            .field("reset", &self.reset)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for IdleReset<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if *this.reset {
+            return Poll::Ready(Err(idle(*this.label)));
+        }
+
+        let last = *this.last.get_or_insert_with(Instant::now);
+        if let Some((armed_for, deadline)) = this.deadline {
+            if deadline.poll(cx).is_ready() {
+                if *armed_for == last {
+                    *this.reset = true;
+                    return Poll::Ready(Err(idle(*this.label)));
+                }
+                // there's been a read since this was armed, so it's early
+                *this.deadline = None;
+            }
+        }
+
+        let result = this.inner.poll_read(cx, buf);
+        match result {
+            Poll::Ready(Ok(_)) => *this.last = Some(Instant::now()),
+            Poll::Pending if this.deadline.is_none() => {
+                let remaining = this.timeout.checked_sub(last.elapsed()).unwrap_or_default();
+                let mut deadline = Deadline::after(this.timer.as_ref(), remaining);
+                if deadline.poll(cx).is_ready() {
+                    *this.reset = true;
+                    return Poll::Ready(Err(idle(*this.label)));
+                }
+                *this.deadline = Some((last, deadline));
+            }
+            _ => (),
+        }
+        result
+    }
+}
+
+fn idle(label: Option<&'static str>) -> io::Error {
+    label::error(
+        label,
+        io::ErrorKind::ConnectionReset,
+        "connection idle for too long",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use futures::future;
+    use futures::io;
+    use futures::task::noop_waker_ref;
+    use futures::task::Context;
+    use futures::task::Poll;
+    use futures::AsyncRead;
+
+    use crate::IdleReset;
+    use crate::ReadEvent;
+    use crate::Scripted;
+    use crate::Sleep;
+    use crate::Timer;
+
+    /// A `Timer` whose sleeps complete only once its clock has been moved past them.
+    #[derive(Clone, Default)]
+    struct MockClock {
+        now: Arc<Mutex<Duration>>,
+    }
+
+    impl MockClock {
+        fn advance(&self, by: Duration) {
+            *self.now.lock().unwrap() += by;
+        }
+    }
+
+    impl Timer for MockClock {
+        fn sleep(&self, duration: Duration) -> Sleep {
+            let now = self.now.clone();
+            let at = *now.lock().unwrap() + duration;
+            Box::pin(future::poll_fn(move |_cx| {
+                if *now.lock().unwrap() >= at {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            }))
+        }
+    }
+
+    #[test]
+    fn resets() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let clock = MockClock::default();
+        let mut r = IdleReset::new(
+            Scripted::new(vec![
+                ReadEvent::Data(b"a".to_vec()),
+                ReadEvent::Pending,
+                ReadEvent::Data(b"b".to_vec()),
+                ReadEvent::Pending,
+                ReadEvent::Pending,
+            ]),
+            Duration::from_secs(30),
+        )
+        .with_label("session");
+        r.set_timer(Arc::new(clock.clone()));
+
+        let mut buf = [0u8; 1];
+        let mut read = |r: &mut IdleReset<Scripted>| match Pin::new(r).poll_read(&mut cx, &mut buf)
+        {
+            Poll::Ready(Ok(n)) => Some(Ok(n)),
+            Poll::Ready(Err(e)) => Some(Err(e.kind())),
+            Poll::Pending => None,
+        };
+
+        assert_eq!(Some(Ok(1)), read(&mut r));
+        assert_eq!(None, read(&mut r));
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(Some(Ok(1)), read(&mut r));
+
+        // the sleep armed before the read fires early, and is re-armed
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(None, read(&mut r));
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(None, read(&mut r));
+
+        clock.advance(Duration::from_secs(20));
+        for _ in 0..2 {
+            assert_eq!(Some(Err(io::ErrorKind::ConnectionReset)), read(&mut r));
+        }
+        match Pin::new(&mut r).poll_read(&mut cx, &mut buf) {
+            Poll::Ready(Err(e)) => {
+                assert_eq!("session: connection idle for too long", e.to_string())
+            }
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+}
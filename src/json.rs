@@ -0,0 +1,71 @@
+/// Finds the end of a JSON value, without parsing it, so a complete value can be buffered
+/// before handing it to a real parser.
+///
+/// The scan is resumable: feed it the same, growing, buffer until it finds the end.
+#[derive(Default)]
+pub(crate) struct Scan {
+    pos: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+fn is_delimiter(b: u8) -> bool {
+    b" \t\r\n,:{}[]\"".contains(&b)
+}
+
+impl Scan {
+    /// Returns the length of the value at the start of `buf`, or `None` if the end has not
+    /// been seen yet. A `buf` starting with whitespace, or another delimiter, is an error.
+    ///
+    /// A bare scalar, like a number, only ends at a delimiter, or the end of the stream;
+    /// see `end_of_stream`.
+    pub(crate) fn scan(&mut self, buf: &[u8]) -> Result<Option<usize>, &'static str> {
+        let scalar = match buf.first() {
+            Some(b'{') | Some(b'[') | Some(b'"') => false,
+            Some(b'}') | Some(b']') => return Err("unbalanced close"),
+            Some(&b) if is_delimiter(b) => return Err("value starts with a delimiter"),
+            Some(_) => true,
+            None => return Ok(None),
+        };
+
+        while self.pos < buf.len() {
+            let b = buf[self.pos];
+            self.pos += 1;
+            if scalar {
+                if is_delimiter(b) {
+                    return Ok(Some(self.pos - 1));
+                }
+            } else if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b'\\' == b {
+                    self.escaped = true;
+                } else if b'"' == b {
+                    self.in_string = false;
+                    if 0 == self.depth {
+                        return Ok(Some(self.pos));
+                    }
+                }
+            } else {
+                match b {
+                    b'"' => self.in_string = true,
+                    b'{' | b'[' => self.depth += 1,
+                    b'}' | b']' => {
+                        self.depth -= 1;
+                        if 0 == self.depth {
+                            return Ok(Some(self.pos));
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether a value which has not ended is complete at the end of the stream.
+    pub(crate) fn end_of_stream(&self) -> bool {
+        0 == self.depth && !self.in_string
+    }
+}
@@ -0,0 +1,14 @@
+use std::fmt;
+use std::io;
+
+/// Build an error generated by a wrapper, prefixed with the wrapper's label, if it has one.
+pub(crate) fn error(
+    label: Option<&'static str>,
+    kind: io::ErrorKind,
+    msg: impl fmt::Display,
+) -> io::Error {
+    match label {
+        Some(label) => io::Error::new(kind, format!("{}: {}", label, msg)),
+        None => io::Error::new(kind, msg.to_string()),
+    }
+}
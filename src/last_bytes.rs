@@ -0,0 +1,87 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+use slice_deque::SliceDeque;
+
+pin_project! {
+    /// Remember the last `capacity` bytes read from the inner reader, to dump when
+    /// something goes wrong. Reads are passed through unchanged.
+    ///
+    /// Unlike `Transcript`, the memory used is bounded.
+    pub struct LastBytes<R> {
+        #[pin]
+        inner: R,
+        capacity: usize,
+        recent: SliceDeque<u8>,
+    }
+}
+
+impl<R> LastBytes<R> {
+    pub fn new(inner: R, capacity: usize) -> Self {
+        LastBytes {
+            inner,
+            capacity,
+            recent: SliceDeque::with_capacity(capacity),
+        }
+    }
+
+    /// The last `capacity` bytes read, or everything, if less has been read.
+    pub fn recent(&self) -> &[u8] {
+        &self.recent
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for LastBytes<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        let keep = found.min(*this.capacity);
+        let excess = (this.recent.len() + keep).saturating_sub(*this.capacity);
+        this.recent.drain(..excess);
+        this.recent.extend_from_slice(&buf[found - keep..found]);
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::LastBytes;
+    use crate::ShortRead;
+
+    #[test]
+    fn bounded() {
+        task::block_on(async {
+            let mut r = LastBytes::new(
+                ShortRead::new(
+                    io::Cursor::new(b"hello world, goodbye"),
+                    vec![3, 0, 2, 10, 5].into_iter(),
+                ),
+                8,
+            );
+            let mut buf = [0u8; 5];
+            r.read_exact(&mut buf).await.unwrap();
+            assert_eq!(b"hello", r.recent());
+
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).await.unwrap();
+            assert_eq!(b" goodbye", r.recent());
+        });
+    }
+}
@@ -0,0 +1,223 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncWrite;
+use pin_project_lite::pin_project;
+
+use crate::label;
+
+pin_project! {
+    /// Buffer writes until `flush`, then emit them as a single frame:
+    /// a big-endian `u32` length, followed by the payload.
+    ///
+    /// By default, a `flush` with nothing buffered writes nothing; see `set_empty_frames`.
+    /// `close` emits any remaining buffered data as a final frame.
+    ///
+    /// ```rust
+    /// use futures::io::AsyncWriteExt as _;
+    /// # async_std::task::block_on(async {
+    /// let mut w = aiowrap::LengthPrefixWriter::new(Vec::new());
+    /// w.write_all(b"hi").await.unwrap();
+    /// w.flush().await.unwrap();
+    /// assert_eq!(&[0, 0, 0, 2, b'h', b'i'], w.get_ref().as_slice());
+    /// # });
+    /// ```
+    pub struct LengthPrefixWriter<W> {
+        #[pin]
+        inner: W,
+        buf: Vec<u8>,
+        frame: Vec<u8>,
+        written: usize,
+        flushing: bool,
+        empty_frames: bool,
+        label: Option<&'static str>,
+    }
+}
+
+impl<W> LengthPrefixWriter<W> {
+    pub fn new(inner: W) -> Self {
+        LengthPrefixWriter {
+            inner,
+            buf: Vec::new(),
+            frame: Vec::new(),
+            written: 0,
+            flushing: false,
+            empty_frames: false,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// generated by this writer.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// If enabled, a `flush` with nothing buffered emits a zero-length frame, instead of nothing.
+    pub fn set_empty_frames(&mut self, enabled: bool) {
+        self.empty_frames = enabled;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Note that any data which has not yet been flushed is lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: fmt::Debug> fmt::Debug for LengthPrefixWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LengthPrefixWriter")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+impl<W: AsyncWrite> LengthPrefixWriter<W> {
+    fn poll_emit(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        allow_empty: bool,
+    ) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+
+        if !*this.flushing && (!this.buf.is_empty() || allow_empty) {
+            let len = u32::try_from(this.buf.len()).expect("checked by poll_write");
+            this.frame.extend_from_slice(&len.to_be_bytes());
+            this.frame.append(this.buf);
+            *this.flushing = true;
+        }
+
+        while *this.written < this.frame.len() {
+            let n = ready!(this
+                .inner
+                .as_mut()
+                .poll_write(cx, &this.frame[*this.written..]))?;
+            if 0 == n {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            *this.written += n;
+        }
+
+        this.frame.clear();
+        *this.written = 0;
+
+        ready!(this.inner.poll_flush(cx))?;
+        *this.flushing = false;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for LengthPrefixWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let space = u32::MAX as usize - this.buf.len();
+        if 0 == space && !buf.is_empty() {
+            return Poll::Ready(Err(label::error(
+                *this.label,
+                io::ErrorKind::InvalidInput,
+                "frame would exceed u32::MAX bytes",
+            )));
+        }
+        let using = space.min(buf.len());
+        this.buf.extend_from_slice(&buf[..using]);
+        Poll::Ready(Ok(using))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let allow_empty = self.empty_frames;
+        self.poll_emit(cx, allow_empty)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_emit(cx, false))?;
+        self.project().inner.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncWriteExt as _;
+
+    use crate::DequeReader;
+    use crate::LengthPrefixWriter;
+    use crate::ShortRead;
+
+    async fn read_frame<R: io::AsyncRead + Unpin>(r: &mut DequeReader<R>) -> Vec<u8> {
+        use futures::io::AsyncBufRead as _;
+        use std::pin::Pin;
+
+        while r.buffer().len() < 4 {
+            assert!(r.read_more().await.unwrap());
+        }
+        let len = u32::from_be_bytes(r.buffer()[..4].try_into().unwrap()) as usize;
+        while r.buffer().len() < 4 + len {
+            assert!(r.read_more().await.unwrap());
+        }
+        let frame = r.buffer()[4..4 + len].to_vec();
+        Pin::new(r).consume(4 + len);
+        frame
+    }
+
+    #[test]
+    fn two_frames() {
+        task::block_on(async {
+            let mut w = LengthPrefixWriter::new(Vec::new());
+            w.write_all(b"hello").await.unwrap();
+            w.write_all(b" world").await.unwrap();
+            w.flush().await.unwrap();
+            // nothing buffered, so nothing written
+            w.flush().await.unwrap();
+            w.write_all(b"bye").await.unwrap();
+            w.close().await.unwrap();
+
+            let written = w.into_inner();
+            assert_eq!(4 + 11 + 4 + 3, written.len());
+
+            let mut r = DequeReader::new(ShortRead::new(
+                io::Cursor::new(written),
+                vec![1, 2, 3, 4, 5, 6, 7].into_iter(),
+            ));
+            assert_eq!(b"hello world", read_frame(&mut r).await.as_slice());
+            assert_eq!(b"bye", read_frame(&mut r).await.as_slice());
+            assert!(!r.read_more().await.unwrap());
+        });
+    }
+
+    #[test]
+    fn empty_frames() {
+        task::block_on(async {
+            let mut w = LengthPrefixWriter::new(Vec::new()).with_label("frames");
+            w.set_empty_frames(true);
+            w.flush().await.unwrap();
+            w.write_all(b"a").await.unwrap();
+            w.flush().await.unwrap();
+            assert_eq!(&[0, 0, 0, 0, 0, 0, 0, 1, b'a'], w.get_ref().as_slice());
+            let debug = format!("{:?}", w);
+            assert!(debug.contains("label: Some(\"frames\")"), "{}", debug);
+        });
+    }
+}
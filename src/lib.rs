@@ -1,5 +1,112 @@
+mod bit_rot;
+mod bounded_write;
+mod burst;
+mod chaos;
+mod crc32;
+mod dedup;
+mod delay_first;
 mod deque_reader;
+mod error_read;
+mod failover;
+mod fragment;
+mod half_open;
+mod idle_reset;
+mod json;
+mod label;
+mod last_bytes;
+mod length_prefix;
+mod live;
+mod max_polls;
+mod memory_budget;
+mod mtu_write;
+mod nagle;
+mod newlines;
+mod offset;
+mod once_eof;
+mod pausable;
+mod ramp;
+mod read_sizes;
+mod readiness_spy;
+mod reorder_write;
+mod require_min;
+mod retransmit;
+mod rng;
+mod round_robin;
+mod scripted;
 mod short;
+mod split_read;
+mod stall_write;
+mod stateful_framed;
+mod strip_trailer;
+mod tee_hash;
+mod timeline;
+mod timer;
+mod transcript;
+mod transform;
+mod validate;
+mod var_len_framed;
+mod would_block;
+mod xor;
 
+pub use bit_rot::BitRot;
+pub use bounded_write::BoundedWrite;
+pub use burst::BurstRead;
+pub use chaos::Chaos;
+pub use crc32::crc32;
+pub use dedup::Dedup;
+pub use delay_first::DelayFirst;
 pub use deque_reader::DequeReader;
+pub use deque_reader::FillEvent;
+pub use deque_reader::LimitedDequeReader;
+pub use deque_reader::Step;
+pub use error_read::ErrorRead;
+pub use failover::Failover;
+pub use fragment::Fragment;
+pub use half_open::HalfOpen;
+pub use half_open::WriteOnly;
+pub use idle_reset::IdleReset;
+pub use last_bytes::LastBytes;
+pub use length_prefix::LengthPrefixWriter;
+pub use live::LiveReader;
+pub use live::LiveWriter;
+pub use max_polls::MaxPolls;
+pub use memory_budget::MemoryBudget;
+pub use mtu_write::MtuWrite;
+pub use nagle::Nagle;
+pub use newlines::NormalizeNewlines;
+pub use offset::OffsetTracker;
+pub use once_eof::OnceEof;
+pub use pausable::Pausable;
+pub use pausable::PauseHandle;
+pub use ramp::RampRead;
+pub use read_sizes::ReadSizes;
+pub use readiness_spy::ReadinessSpy;
+pub use reorder_write::ReorderWrite;
+pub use require_min::RequireMin;
+pub use retransmit::Retransmit;
+pub use round_robin::RoundRobinBuffer;
+pub use round_robin::RoundRobinConsumer;
+pub use scripted::ReadEvent;
+pub use scripted::Scripted;
 pub use short::ShortRead;
+pub use split_read::SplitRead;
+pub use stall_write::StallWrite;
+pub use stateful_framed::FrameOutcome;
+pub use stateful_framed::FrameState;
+pub use stateful_framed::StatefulFramed;
+pub use strip_trailer::StripTrailer;
+pub use tee_hash::TeeHash;
+pub use timeline::Timeline;
+pub use timeline::TimelineRecorder;
+pub use timeline::TimelineReplay;
+pub use timer::Sleep;
+pub use timer::ThreadTimer;
+pub use timer::Timer;
+pub use transcript::Transcript;
+pub use transform::StreamTransform;
+pub use transform::Transform;
+pub use validate::Validate;
+pub use var_len_framed::LengthField;
+pub use var_len_framed::VarLenFramed;
+pub use would_block::WouldBlockRead;
+pub use xor::XorStream;
@@ -0,0 +1,128 @@
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::task::Waker;
+use futures::AsyncRead;
+
+#[derive(Default)]
+struct State {
+    data: VecDeque<u8>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+/// A reader of data which is appended, while it is being read, through a `LiveWriter`;
+/// modelling a live socket more faithfully than a `Cursor`.
+///
+/// When no data is available, reads return `Pending`, and the task is woken when more is
+/// appended. Once the writer is closed, or dropped, and the data is exhausted, reads
+/// return EOF.
+pub struct LiveReader {
+    state: Arc<Mutex<State>>,
+}
+
+/// Appends data for a `LiveReader`, from anywhere.
+pub struct LiveWriter {
+    state: Arc<Mutex<State>>,
+}
+
+impl LiveReader {
+    pub fn new() -> (LiveReader, LiveWriter) {
+        let state = Arc::new(Mutex::new(State::default()));
+        (
+            LiveReader {
+                state: state.clone(),
+            },
+            LiveWriter { state },
+        )
+    }
+}
+
+impl LiveWriter {
+    /// Make more data available to the reader, waking it if it is waiting.
+    pub fn append(&self, data: &[u8]) {
+        self.update(|state| state.data.extend(data));
+    }
+
+    /// End the stream, once the reader has read the data already appended.
+    pub fn close(&self) {
+        self.update(|state| state.closed = true);
+    }
+
+    fn update(&self, f: impl FnOnce(&mut State)) {
+        let waker = {
+            let mut state = self.state.lock().expect("poisoned");
+            f(&mut state);
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for LiveWriter {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl AsyncRead for LiveReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.state.lock().expect("poisoned");
+        if state.data.is_empty() && !state.closed && !buf.is_empty() {
+            state.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let using = state.data.len().min(buf.len());
+        for (to, from) in buf.iter_mut().zip(state.data.drain(..using)) {
+            *to = from;
+        }
+        Poll::Ready(Ok(using))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::future::FutureExt as _;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::LiveReader;
+
+    #[test]
+    fn appended() {
+        task::block_on(async {
+            let (mut r, w) = LiveReader::new();
+            let mut buf = [0u8; 16];
+            assert!(r.read(&mut buf).now_or_never().is_none());
+
+            let writer = task::spawn(async move {
+                for part in &[&b"hello"[..], b" ", b"world"] {
+                    w.append(part);
+                    task::yield_now().await;
+                }
+                w.close();
+            });
+
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).await.unwrap();
+            assert_eq!(b"hello world", out.as_slice());
+            writer.await;
+        });
+    }
+}
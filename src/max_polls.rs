@@ -0,0 +1,139 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::label;
+
+pin_project! {
+    /// Fail once `poll_read` has been called more than `max` times, to catch code
+    /// which spins, instead of hanging the test.
+    ///
+    /// By default, the extra poll returns an `io::ErrorKind::Other` error; see `set_panic`.
+    ///
+    /// ```rust
+    /// use futures::io;
+    /// use futures::io::AsyncReadExt as _;
+    /// # async_std::task::block_on(async {
+    /// let mut r = aiowrap::MaxPolls::new(io::Cursor::new(b"hello"), 1);
+    /// let mut buf = [0u8; 5];
+    /// assert_eq!(5, r.read(&mut buf).await.unwrap());
+    /// assert!(r.read(&mut buf).await.is_err());
+    /// # });
+    /// ```
+    pub struct MaxPolls<R> {
+        #[pin]
+        inner: R,
+        polls: usize,
+        max: usize,
+        panic: bool,
+        label: Option<&'static str>,
+    }
+}
+
+impl<R> MaxPolls<R> {
+    pub fn new(inner: R, max: usize) -> Self {
+        MaxPolls {
+            inner,
+            polls: 0,
+            max,
+            panic: false,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// and panics generated by this reader.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Panic, instead of returning an error, when the limit is exceeded.
+    pub fn set_panic(&mut self, enabled: bool) {
+        self.panic = enabled;
+    }
+
+    /// The number of times `poll_read` has been called.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for MaxPolls<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MaxPolls")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("polls", &self.polls)
+            .field("max", &self.max)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for MaxPolls<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        *this.polls += 1;
+        if *this.polls > *this.max {
+            let msg = format!("poll_read called more than {} times", this.max);
+            let err = label::error(*this.label, io::ErrorKind::Other, msg);
+            if *this.panic {
+                panic!("{}", err);
+            }
+            return Poll::Ready(Err(err));
+        }
+        this.inner.poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::MaxPolls;
+    use crate::ShortRead;
+
+    /// A reader which is always `Pending`, but asks to be polled again immediately.
+    fn spinning() -> ShortRead<io::Cursor<&'static [u8]>, iter::Repeat<usize>> {
+        ShortRead::new(io::Cursor::new(b"never"), iter::repeat(0))
+    }
+
+    #[test]
+    fn capped() {
+        task::block_on(async {
+            let mut r = MaxPolls::new(spinning(), 100);
+            let mut buf = [0u8; 5];
+            let err = r.read(&mut buf).await.unwrap_err();
+            assert_eq!(io::ErrorKind::Other, err.kind());
+            assert_eq!(101, r.polls());
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "more than 3 times")]
+    fn panics() {
+        task::block_on(async {
+            let mut r = MaxPolls::new(spinning(), 3);
+            r.set_panic(true);
+            let mut buf = [0u8; 5];
+            let _ = r.read(&mut buf).await;
+        });
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::task::Waker;
+
+struct State {
+    cap: usize,
+    used: usize,
+    waiting: Vec<Waker>,
+}
+
+/// A limit on the total memory buffered by a group of `DequeReader`s, e.g. all the
+/// connections to a server, shared with `DequeReader::with_budget`.
+///
+/// A reader only reads as much as the remaining budget allows; when the budget is
+/// exhausted, its reads return `Pending` until another reader consumes some data.
+/// Note that a reader which needs more than the whole budget to make progress, e.g.
+/// to buffer one large frame, will wait forever.
+#[derive(Clone)]
+pub struct MemoryBudget {
+    state: Arc<Mutex<State>>,
+}
+
+impl MemoryBudget {
+    pub fn new(cap: usize) -> MemoryBudget {
+        MemoryBudget {
+            state: Arc::new(Mutex::new(State {
+                cap,
+                used: 0,
+                waiting: Vec::new(),
+            })),
+        }
+    }
+
+    /// How many bytes are buffered by all the readers using this budget.
+    pub fn used(&self) -> usize {
+        self.state.lock().expect("poisoned").used
+    }
+
+    /// How many more bytes may be buffered.
+    pub fn available(&self) -> usize {
+        let state = self.state.lock().expect("poisoned");
+        state.cap.saturating_sub(state.used)
+    }
+}
+
+/// One reader's share of a `MemoryBudget`, which is returned when dropped.
+pub(crate) struct Charge {
+    budget: MemoryBudget,
+    held: usize,
+}
+
+impl Charge {
+    pub(crate) fn new(budget: MemoryBudget) -> Charge {
+        Charge { budget, held: 0 }
+    }
+
+    /// Take up to `wanted` bytes from the budget, waiting if none are available.
+    pub(crate) fn poll_reserve(&mut self, cx: &mut Context, wanted: usize) -> Poll<usize> {
+        let mut state = self.budget.state.lock().expect("poisoned");
+        let granted = wanted.min(state.cap.saturating_sub(state.used));
+        if 0 == granted && 0 != wanted {
+            if !state.waiting.iter().any(|w| w.will_wake(cx.waker())) {
+                state.waiting.push(cx.waker().clone());
+            }
+            return Poll::Pending;
+        }
+        state.used += granted;
+        self.held += granted;
+        Poll::Ready(granted)
+    }
+
+    /// Record that the reader is now buffering `len` bytes, returning anything it has
+    /// stopped using to the budget, and waking any readers waiting for it.
+    pub(crate) fn settle(&mut self, len: usize) {
+        let waiting = {
+            let mut state = self.budget.state.lock().expect("poisoned");
+            state.used = state.used - self.held + len;
+            let freed = len < self.held;
+            self.held = len;
+            if !freed {
+                return;
+            }
+            std::mem::take(&mut state.waiting)
+        };
+        for waker in waiting {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for Charge {
+    fn drop(&mut self) {
+        if self.budget.state.lock().is_ok() {
+            self.settle(0);
+        }
+    }
+}
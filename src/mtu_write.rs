@@ -0,0 +1,81 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncWrite;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Accept at most `mtu` bytes per write, to test packetization on the write side.
+    ///
+    /// Unlike `StallWrite`, the limit is fixed, and writes are never stalled.
+    pub struct MtuWrite<W> {
+        #[pin]
+        inner: W,
+        mtu: usize,
+    }
+}
+
+impl<W> MtuWrite<W> {
+    /// # Panics
+    ///
+    /// Panics if `mtu` is zero.
+    pub fn new(inner: W, mtu: usize) -> Self {
+        assert!(mtu > 0, "mtu must be non-zero");
+        MtuWrite { inner, mtu }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for MtuWrite<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let wanted = buf.len().min(*this.mtu);
+        this.inner.poll_write(cx, &buf[..wanted])
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io::AsyncWriteExt as _;
+
+    use crate::MtuWrite;
+
+    #[test]
+    fn increments() {
+        task::block_on(async {
+            let data = vec![5u8; 5000];
+            let mut w = MtuWrite::new(Vec::new(), 1500);
+            let mut accepted = Vec::new();
+            let mut pos = 0;
+            while pos < data.len() {
+                let n = w.write(&data[pos..]).await.unwrap();
+                accepted.push(n);
+                pos += n;
+            }
+            assert_eq!(vec![1500, 1500, 1500, 500], accepted);
+            assert_eq!(data, w.into_inner());
+        });
+    }
+}
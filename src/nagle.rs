@@ -0,0 +1,249 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncWrite;
+use pin_project_lite::pin_project;
+
+use crate::timer::Deadline;
+use crate::Timer;
+
+pin_project! {
+    /// Coalesce small writes, like TCP's Nagle algorithm, to test write batching.
+    ///
+    /// Writes smaller than `threshold` are buffered, and forwarded to the inner writer as
+    /// one write as soon as `threshold` bytes are buffered, or by the next write once the
+    /// oldest buffered data is older than `delay`; the writing task is woken when the delay
+    /// passes. Buffered data is always forwarded by `poll_flush` and `poll_close`.
+    ///
+    /// Writes of at least `threshold` bytes, when nothing is buffered, are passed straight through.
+    pub struct Nagle<W> {
+        #[pin]
+        inner: W,
+        threshold: usize,
+        delay: Duration,
+        pending: Vec<u8>,
+        deadline: Option<Deadline>,
+        timer: Option<Arc<dyn Timer>>,
+    }
+}
+
+impl<W> Nagle<W> {
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn new(inner: W, threshold: usize, delay: Duration) -> Self {
+        assert!(threshold > 0, "threshold must be non-zero");
+        Nagle {
+            inner,
+            threshold,
+            delay,
+            pending: Vec::with_capacity(threshold),
+            deadline: None,
+            timer: None,
+        }
+    }
+
+    /// Use this `Timer` for the delay, instead of the default `ThreadTimer`.
+    pub fn set_timer(&mut self, timer: Arc<dyn Timer>) {
+        self.timer = Some(timer);
+        self.deadline = None;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Note that any buffered data is lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite> Nagle<W> {
+    /// Forward all the buffered data to the inner writer.
+    fn poll_forward(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+        while !this.pending.is_empty() {
+            let written = ready!(this.inner.as_mut().poll_write(cx, this.pending))?;
+            if 0 == written {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            this.pending.drain(..written);
+        }
+        *this.deadline = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for Nagle<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let expired = match self.as_mut().project().deadline {
+            Some(deadline) => deadline.poll(cx).is_ready(),
+            None => false,
+        };
+        if expired || self.pending.len() >= self.threshold {
+            ready!(self.as_mut().poll_forward(cx))?;
+        }
+
+        let this = self.as_mut().project();
+        if this.pending.is_empty() && buf.len() >= *this.threshold {
+            return this.inner.poll_write(cx, buf);
+        }
+
+        let using = buf.len().min(*this.threshold - this.pending.len());
+        this.pending.extend_from_slice(&buf[..using]);
+        if this.deadline.is_none() {
+            let mut deadline = Deadline::after(this.timer.as_ref(), *this.delay);
+            // register for the wakeup
+            let _ = deadline.poll(cx);
+            *this.deadline = Some(deadline);
+        }
+        if this.pending.len() == *this.threshold {
+            // the data is accepted either way; a failure recurs on the next forward
+            let _ = self.poll_forward(cx);
+        }
+        Poll::Ready(Ok(using))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_forward(cx))?;
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_forward(cx))?;
+        self.project().inner.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use async_std::task;
+    use futures::future;
+    use futures::io::AsyncWriteExt as _;
+    use futures::task::Context;
+    use futures::task::Poll;
+    use futures::AsyncWrite;
+
+    use crate::Nagle;
+    use crate::Sleep;
+    use crate::Timer;
+
+    /// Records each write separately.
+    #[derive(Default)]
+    struct Writes(Vec<Vec<u8>>);
+
+    impl AsyncWrite for Writes {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.0.push(buf.to_vec());
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn coalesces() {
+        task::block_on(async {
+            let mut w = Nagle::new(Writes::default(), 10, Duration::from_secs(60));
+            for part in &[b"abc", b"def", b"ghi"] {
+                w.write_all(*part).await.unwrap();
+            }
+            assert!(w.get_ref().0.is_empty());
+
+            w.write_all(b"jkl").await.unwrap();
+            assert_eq!(vec![b"abcdefghij".to_vec()], w.get_ref().0);
+
+            w.write_all(&[b'z'; 20]).await.unwrap();
+            w.close().await.unwrap();
+            assert_eq!(
+                vec![
+                    b"abcdefghij".to_vec(),
+                    b"klzzzzzzzz".to_vec(),
+                    vec![b'z'; 12]
+                ],
+                w.into_inner().0
+            );
+        });
+    }
+
+    #[test]
+    fn forwards_when_full() {
+        task::block_on(async {
+            let mut w = Nagle::new(Writes::default(), 4, Duration::from_secs(60));
+            w.write_all(b"ab").await.unwrap();
+            w.write_all(b"cd").await.unwrap();
+            assert_eq!(vec![b"abcd".to_vec()], w.get_ref().0);
+        });
+    }
+
+    /// A `Timer` whose sleeps complete only once its clock has been moved past them.
+    #[derive(Clone, Default)]
+    struct MockClock {
+        now: Arc<Mutex<Duration>>,
+    }
+
+    impl MockClock {
+        fn advance(&self, by: Duration) {
+            *self.now.lock().unwrap() += by;
+        }
+    }
+
+    impl Timer for MockClock {
+        fn sleep(&self, duration: Duration) -> Sleep {
+            let now = self.now.clone();
+            let at = *now.lock().unwrap() + duration;
+            Box::pin(future::poll_fn(move |_cx| {
+                if *now.lock().unwrap() >= at {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            }))
+        }
+    }
+
+    #[test]
+    fn delay() {
+        task::block_on(async {
+            let clock = MockClock::default();
+            let mut w = Nagle::new(Writes::default(), 10, Duration::from_millis(10));
+            w.set_timer(Arc::new(clock.clone()));
+            w.write_all(b"abc").await.unwrap();
+            clock.advance(Duration::from_millis(5));
+            w.write_all(b"def").await.unwrap();
+            assert!(w.get_ref().0.is_empty());
+
+            clock.advance(Duration::from_millis(5));
+            w.write_all(b"ghi").await.unwrap();
+            assert_eq!(vec![b"abcdef".to_vec()], w.get_ref().0);
+            w.flush().await.unwrap();
+            assert_eq!(vec![b"abcdef".to_vec(), b"ghi".to_vec()], w.into_inner().0);
+        });
+    }
+}
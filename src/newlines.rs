@@ -0,0 +1,100 @@
+use std::io;
+
+use slice_deque::SliceDeque;
+
+use crate::StreamTransform;
+
+/// A `StreamTransform` which converts line endings, for use with `Transform`.
+///
+/// `\r\n`, lone `\r`, and lone `\n` are all converted to the chosen line ending,
+/// wherever the reads of the stream happen to be split.
+///
+/// ```rust
+/// use futures::io;
+/// use futures::io::AsyncReadExt as _;
+/// use aiowrap::NormalizeNewlines;
+/// use aiowrap::Transform;
+/// # async_std::task::block_on(async {
+/// let mut r = Transform::new(io::Cursor::new(b"a\r\nb\rc\n"), NormalizeNewlines::to_lf());
+/// let mut out = Vec::new();
+/// r.read_to_end(&mut out).await.unwrap();
+/// assert_eq!(b"a\nb\nc\n", out.as_slice());
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct NormalizeNewlines {
+    ending: &'static [u8],
+    after_cr: bool,
+}
+
+impl NormalizeNewlines {
+    /// Convert all line endings to `\n`.
+    pub fn to_lf() -> Self {
+        NormalizeNewlines {
+            ending: b"\n",
+            after_cr: false,
+        }
+    }
+
+    /// Convert all line endings to `\r\n`.
+    pub fn to_crlf() -> Self {
+        NormalizeNewlines {
+            ending: b"\r\n",
+            after_cr: false,
+        }
+    }
+}
+
+impl StreamTransform for NormalizeNewlines {
+    fn transform(&mut self, input: &[u8], output: &mut SliceDeque<u8>) -> io::Result<usize> {
+        for &b in input {
+            match b {
+                // the line ending is emitted straight away;
+                // a `\n` following it, maybe in the next read, is dropped
+                b'\r' => output.extend_from_slice(self.ending),
+                b'\n' if self.after_cr => (),
+                b'\n' => output.extend_from_slice(self.ending),
+                other => output.extend_from_slice(&[other]),
+            }
+            self.after_cr = b'\r' == b;
+        }
+        Ok(input.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::NormalizeNewlines;
+    use crate::ShortRead;
+    use crate::Transform;
+
+    async fn normalize(input: &[u8], sizes: Vec<usize>, to: NormalizeNewlines) -> Vec<u8> {
+        let mut r = Transform::new(
+            ShortRead::new(io::Cursor::new(input), sizes.into_iter()),
+            to,
+        );
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).await.unwrap();
+        out
+    }
+
+    #[test]
+    fn split_crlf() {
+        task::block_on(async {
+            let input = b"ab\r\ncd\r\n\r\r\ne\n";
+            let sizes = vec![3, 0, 1, 3, 1, 1, 1, 9];
+            assert_eq!(
+                b"ab\ncd\n\n\ne\n".to_vec(),
+                normalize(input, sizes.clone(), NormalizeNewlines::to_lf()).await
+            );
+            assert_eq!(
+                b"ab\r\ncd\r\n\r\n\r\ne\r\n".to_vec(),
+                normalize(input, sizes, NormalizeNewlines::to_crlf()).await
+            );
+        });
+    }
+}
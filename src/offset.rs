@@ -0,0 +1,128 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncBufRead;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Track the logical offset into a stream, for reporting positions in errors.
+    ///
+    /// The offset counts bytes returned by `poll_read`, and bytes passed to `consume`,
+    /// so wrapping a `DequeReader` counts the bytes the parser has consumed, not the
+    /// bytes which have merely been buffered.
+    ///
+    /// ```rust
+    /// use futures::io;
+    /// use futures::io::AsyncReadExt as _;
+    /// # async_std::task::block_on(async {
+    /// let mut r = aiowrap::OffsetTracker::new(io::Cursor::new(b"hello"));
+    /// let mut buf = [0u8; 2];
+    /// r.read_exact(&mut buf).await.unwrap();
+    /// assert_eq!(2, r.offset());
+    /// # });
+    /// ```
+    pub struct OffsetTracker<R> {
+        #[pin]
+        inner: R,
+        offset: u64,
+    }
+}
+
+impl<R> OffsetTracker<R> {
+    pub fn new(inner: R) -> Self {
+        OffsetTracker { inner, offset: 0 }
+    }
+
+    /// The number of bytes read, or consumed, so far, plus any `advance`s.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Move the offset forward, for data skipped without reading through this tracker.
+    pub fn advance(&mut self, n: u64) {
+        self.offset += n;
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for OffsetTracker<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        *this.offset += found as u64;
+        Poll::Ready(Ok(found))
+    }
+}
+
+impl<R: AsyncBufRead> AsyncBufRead for OffsetTracker<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.project().inner.poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.project();
+        *this.offset += amt as u64;
+        this.inner.consume(amt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncBufReadExt as _;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::DequeReader;
+    use crate::OffsetTracker;
+    use crate::ShortRead;
+
+    #[test]
+    fn mixed() {
+        task::block_on(async {
+            let mut r = OffsetTracker::new(DequeReader::new(ShortRead::new(
+                io::Cursor::new(b"hello world, hello"),
+                vec![3, 4, 0, 5, 6].into_iter(),
+            )));
+            let mut buf = [0u8; 2];
+            r.read_exact(&mut buf).await.unwrap();
+            assert_eq!(2, r.offset());
+
+            // buffered, but not consumed
+            assert!(r.get_mut().read_more().await.unwrap());
+            assert_eq!(2, r.offset());
+
+            let mut line = Vec::new();
+            r.read_until(b',', &mut line).await.unwrap();
+            assert_eq!(b"llo world,", line.as_slice());
+            assert_eq!(12, r.offset());
+
+            assert_eq!(1, r.get_mut().skip(1).await.unwrap());
+            r.advance(1);
+
+            let mut rest = Vec::new();
+            r.read_to_end(&mut rest).await.unwrap();
+            assert_eq!(b"hello", rest.as_slice());
+            assert_eq!(18, r.offset());
+        });
+    }
+}
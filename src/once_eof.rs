@@ -0,0 +1,124 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::label;
+
+pin_project! {
+    /// Fail any read after the end of the stream has been reported, to catch callers
+    /// which keep reading past EOF.
+    ///
+    /// By default, such a read returns an `io::ErrorKind::Other` error. In strict mode,
+    /// it panics instead; see `set_strict`.
+    ///
+    /// A zero-length read into an empty buffer is not considered EOF.
+    pub struct OnceEof<R> {
+        #[pin]
+        inner: R,
+        seen_eof: bool,
+        strict: bool,
+        label: Option<&'static str>,
+    }
+}
+
+impl<R> OnceEof<R> {
+    pub fn new(inner: R) -> Self {
+        OnceEof {
+            inner,
+            seen_eof: false,
+            strict: false,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// and panics generated by this reader.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Panic, instead of returning an error, on a read after EOF.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for OnceEof<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnceEof")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("seen_eof", &self.seen_eof)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for OnceEof<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if *this.seen_eof {
+            let err = label::error(*this.label, io::ErrorKind::Other, "read after EOF");
+            if *this.strict {
+                panic!("{}", err);
+            }
+            return Poll::Ready(Err(err));
+        }
+
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        if 0 == found && !buf.is_empty() {
+            *this.seen_eof = true;
+        }
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::OnceEof;
+
+    #[test]
+    fn errors() {
+        task::block_on(async {
+            let mut r = OnceEof::new(io::Cursor::new(b"ab")).with_label("request");
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).await.unwrap();
+            assert_eq!(b"ab", out.as_slice());
+
+            let mut buf = [0u8; 2];
+            let err = r.read(&mut buf).await.unwrap_err();
+            assert_eq!(io::ErrorKind::Other, err.kind());
+            assert_eq!("request: read after EOF", err.to_string());
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "read after EOF")]
+    fn strict() {
+        task::block_on(async {
+            let mut r = OnceEof::new(io::Cursor::new(b""));
+            r.set_strict(true);
+            let mut buf = [0u8; 2];
+            assert_eq!(0, r.read(&mut buf).await.unwrap());
+            let _ = r.read(&mut buf).await;
+        });
+    }
+}
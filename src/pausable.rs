@@ -0,0 +1,139 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::task::Waker;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+#[derive(Default)]
+struct State {
+    paused: bool,
+    waker: Option<Waker>,
+}
+
+pin_project! {
+    /// A reader which can be paused, and later resumed, by the application.
+    ///
+    /// While paused, reads return `Pending`, and the task is woken on `resume`.
+    /// Use `handle` to control the reader while it is being read elsewhere.
+    pub struct Pausable<R> {
+        #[pin]
+        inner: R,
+        handle: PauseHandle,
+    }
+}
+
+/// Pauses and resumes a `Pausable`, from anywhere.
+#[derive(Clone, Default)]
+pub struct PauseHandle {
+    state: Arc<Mutex<State>>,
+}
+
+impl PauseHandle {
+    /// Make subsequent reads return `Pending`, until `resume` is called.
+    pub fn pause(&self) {
+        self.state.lock().expect("poisoned").paused = true;
+    }
+
+    /// Allow reads again, waking any task waiting for a read.
+    pub fn resume(&self) {
+        let waker = {
+            let mut state = self.state.lock().expect("poisoned");
+            state.paused = false;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().expect("poisoned").paused
+    }
+}
+
+impl<R> Pausable<R> {
+    pub fn new(inner: R) -> Self {
+        Pausable {
+            inner,
+            handle: PauseHandle::default(),
+        }
+    }
+
+    /// A handle for controlling this reader, which can be moved to other tasks.
+    pub fn handle(&self) -> PauseHandle {
+        self.handle.clone()
+    }
+
+    pub fn pause(&self) {
+        self.handle.pause()
+    }
+
+    pub fn resume(&self) {
+        self.handle.resume()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Pausable<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        {
+            let mut state = this.handle.state.lock().expect("poisoned");
+            if state.paused {
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+        this.inner.poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::Pausable;
+
+    #[test]
+    fn pause_resume() {
+        task::block_on(async {
+            let mut r = Pausable::new(io::Cursor::new(b"abcd"));
+            let mut buf = [0u8; 2];
+            assert_eq!(2, r.read(&mut buf).await.unwrap());
+            assert_eq!(b"ab", &buf);
+
+            let handle = r.handle();
+            r.pause();
+            assert!(handle.is_paused());
+
+            let parked = Duration::from_millis(20);
+            let resumer = task::spawn(async move {
+                task::sleep(parked).await;
+                handle.resume();
+            });
+
+            let start = Instant::now();
+            assert_eq!(2, r.read(&mut buf).await.unwrap());
+            assert!(start.elapsed() >= parked);
+            assert_eq!(b"cd", &buf);
+            resumer.await;
+        });
+    }
+}
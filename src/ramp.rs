@@ -0,0 +1,116 @@
+/// A decider for `ShortRead`, producing read sizes which double, from `start`, up to `cap`,
+/// then stay at `cap` forever, much like TCP slow-start.
+///
+/// ```rust
+/// let sizes: Vec<usize> = aiowrap::RampRead::new(1, 10).take(6).collect();
+/// assert_eq!(vec![1, 2, 4, 8, 10, 10], sizes);
+/// ```
+///
+/// The sizes can instead grow by any factor, with `with_rate`, and periodically collapse
+/// back to `start`, then ramp up again, like a congestion event, with `with_collapse`.
+#[derive(Clone, Debug)]
+pub struct RampRead {
+    next: usize,
+    start: usize,
+    cap: usize,
+    rate: usize,
+    collapse: Option<usize>,
+    reads: usize,
+}
+
+impl RampRead {
+    /// # Panics
+    ///
+    /// Panics if `start` is zero, as this would only ever generate `Pending`s.
+    pub fn new(start: usize, cap: usize) -> RampRead {
+        assert!(start > 0, "start must be non-zero");
+        RampRead {
+            next: start.min(cap),
+            start: start.min(cap),
+            cap,
+            rate: 2,
+            collapse: None,
+            reads: 0,
+        }
+    }
+
+    /// Multiply the size by `rate` after each read, instead of doubling it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is zero.
+    pub fn with_rate(mut self, rate: usize) -> RampRead {
+        assert!(rate > 0, "rate must be non-zero");
+        self.rate = rate;
+        self
+    }
+
+    /// Drop back to `start` on every `interval`th read, then ramp up again from there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn with_collapse(mut self, interval: usize) -> RampRead {
+        assert!(interval > 0, "interval must be non-zero");
+        self.collapse = Some(interval);
+        self
+    }
+}
+
+impl Iterator for RampRead {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        self.reads += 1;
+        let current = match self.collapse {
+            Some(interval) if self.reads % interval == 0 => self.start,
+            _ => self.next,
+        };
+        self.next = current.saturating_mul(self.rate).min(self.cap);
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::RampRead;
+    use crate::ShortRead;
+
+    #[test]
+    fn sequence() {
+        let sizes: Vec<usize> = RampRead::new(1, 100).take(9).collect();
+        assert_eq!(vec![1, 2, 4, 8, 16, 32, 64, 100, 100], sizes);
+
+        let sizes: Vec<usize> = RampRead::new(3, 20).take(5).collect();
+        assert_eq!(vec![3, 6, 12, 20, 20], sizes);
+    }
+
+    #[test]
+    fn collapse() {
+        let sizes: Vec<usize> = RampRead::new(2, 500)
+            .with_rate(3)
+            .with_collapse(5)
+            .take(12)
+            .collect();
+        assert_eq!(vec![2, 6, 18, 54, 2, 6, 18, 54, 162, 2, 6, 18], sizes);
+
+        let sizes: Vec<usize> = RampRead::new(1, 4).with_collapse(4).take(8).collect();
+        assert_eq!(vec![1, 2, 4, 1, 2, 4, 4, 1], sizes);
+    }
+
+    #[test]
+    fn short_read() {
+        task::block_on(async {
+            let mut r =
+                ShortRead::new(io::Cursor::new(vec![0u8; 20]), RampRead::new(1, 8)).recording();
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).await.unwrap();
+            assert_eq!(20, out.len());
+            assert_eq!(&[1, 2, 4, 8, 5], r.produced_sizes());
+        });
+    }
+}
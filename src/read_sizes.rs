@@ -0,0 +1,73 @@
+/// A decider for `ShortRead`, calling a closure for each read size, forever, so property
+/// tests can generate the read-splitting patterns to explore.
+///
+/// As with any decider, a size of 0 generates a `Pending`.
+///
+/// ```rust
+/// let mut n = 0;
+/// let sizes: Vec<usize> = aiowrap::ReadSizes::new(|| {
+///     n += 1;
+///     n % 3
+/// })
+/// .take(5)
+/// .collect();
+/// assert_eq!(vec![1, 2, 0, 1, 2], sizes);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReadSizes<F> {
+    generate: F,
+}
+
+impl<F: FnMut() -> usize> ReadSizes<F> {
+    pub fn new(generate: F) -> ReadSizes<F> {
+        ReadSizes { generate }
+    }
+}
+
+impl<F: FnMut() -> usize> Iterator for ReadSizes<F> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        Some((self.generate)())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+
+    use crate::DequeReader;
+    use crate::ReadSizes;
+    use crate::ShortRead;
+
+    async fn tokens(sizes: impl Iterator<Item = usize>) -> Vec<Vec<u8>> {
+        let mut m = DequeReader::new(ShortRead::new(
+            io::Cursor::new(b"  the quick  brown\tfox jumps "),
+            sizes,
+        ));
+        let mut found = Vec::new();
+        while let Some(token) = m.next_token(|b| b.is_ascii_whitespace()).await.unwrap() {
+            found.push(token);
+        }
+        found
+    }
+
+    #[test]
+    fn invariant_to_chunking() {
+        task::block_on(async {
+            let expected = tokens(std::iter::once(4096)).await;
+            assert_eq!(5, expected.len());
+
+            for seed in 0..100u32 {
+                // a small LCG, giving sizes from 0 (pending) to 7
+                let mut state = seed;
+                let sizes = ReadSizes::new(move || {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (state >> 16) as usize % 8
+                });
+                assert_eq!(expected, tokens(sizes).await, "seed {}", seed);
+            }
+        });
+    }
+}
@@ -0,0 +1,77 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Count how many times `poll_read` went from returning `Pending` to returning
+    /// `Ready`, to check how a reader is being driven. Reads are passed through unchanged.
+    pub struct ReadinessSpy<R> {
+        #[pin]
+        inner: R,
+        pending: bool,
+        wakeups: usize,
+    }
+}
+
+impl<R> ReadinessSpy<R> {
+    pub fn new(inner: R) -> Self {
+        ReadinessSpy {
+            inner,
+            pending: false,
+            wakeups: 0,
+        }
+    }
+
+    /// The number of `Pending` to `Ready` transitions seen so far.
+    pub fn wakeups(&self) -> usize {
+        self.wakeups
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for ReadinessSpy<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let result = this.inner.poll_read(cx, buf);
+        if *this.pending && result.is_ready() {
+            *this.wakeups += 1;
+        }
+        *this.pending = result.is_pending();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::ReadinessSpy;
+    use crate::ShortRead;
+
+    #[test]
+    fn transitions() {
+        task::block_on(async {
+            let mut r = ReadinessSpy::new(ShortRead::new(
+                io::Cursor::new(b"abc"),
+                vec![0, 1, 0, 0, 1, 1].into_iter(),
+            ));
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).await.unwrap();
+            assert_eq!(b"abc", out.as_slice());
+            assert_eq!(2, r.wakeups());
+        });
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncWrite;
+use pin_project_lite::pin_project;
+
+use crate::rng;
+
+pin_project! {
+    /// Hold writes back, and forward them to the inner writer in a shuffled order, to test
+    /// that reassembly downstream handles reordering.
+    ///
+    /// Each write is accepted whole, and held. Once `batch` writes are held, or on
+    /// `poll_flush` or `poll_close`, the held writes are shuffled, deterministically for
+    /// the `seed`, and forwarded, each as its own write.
+    pub struct ReorderWrite<W> {
+        #[pin]
+        inner: W,
+        batch: usize,
+        state: u64,
+        held: Vec<Vec<u8>>,
+        sending: VecDeque<Vec<u8>>,
+    }
+}
+
+impl<W> ReorderWrite<W> {
+    /// # Panics
+    ///
+    /// Panics if `batch` is zero.
+    pub fn new(inner: W, batch: usize, seed: u64) -> Self {
+        assert!(batch > 0, "batch must be non-zero");
+        ReorderWrite {
+            inner,
+            batch,
+            state: seed,
+            held: Vec::with_capacity(batch),
+            sending: VecDeque::with_capacity(batch),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Note that any held writes are lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite> ReorderWrite<W> {
+    /// Shuffle the held writes, then forward them, and anything already being forwarded.
+    fn poll_release(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+        // Fisher-Yates
+        for i in (1..this.held.len()).rev() {
+            let j = (rng::next(this.state) % (i as u64 + 1)) as usize;
+            this.held.swap(i, j);
+        }
+        this.sending.extend(this.held.drain(..));
+
+        while let Some(front) = this.sending.front_mut() {
+            let written = ready!(this.inner.as_mut().poll_write(cx, front))?;
+            if 0 == written {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            front.drain(..written);
+            if front.is_empty() {
+                this.sending.pop_front();
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for ReorderWrite<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.held.len() >= self.batch || !self.sending.is_empty() {
+            ready!(self.as_mut().poll_release(cx))?;
+        }
+        if !buf.is_empty() {
+            self.project().held.push(buf.to_vec());
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_release(cx))?;
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_release(cx))?;
+        self.project().inner.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io::AsyncWriteExt as _;
+
+    use crate::MtuWrite;
+    use crate::ReorderWrite;
+
+    async fn reorder(seed: u64) -> Vec<u8> {
+        let mut w = ReorderWrite::new(MtuWrite::new(Vec::new(), 2), 3, seed);
+        for part in &[&b"aaa"[..], b"bb", b"c"] {
+            w.write_all(part).await.unwrap();
+        }
+        assert!(w.get_ref().get_ref().is_empty());
+        w.flush().await.unwrap();
+        w.into_inner().into_inner()
+    }
+
+    #[test]
+    fn permuted() {
+        task::block_on(async {
+            assert_eq!(b"cbbaaa".to_vec(), reorder(3).await);
+            assert_eq!(b"cbbaaa".to_vec(), reorder(3).await);
+            assert_eq!(b"bbaaac".to_vec(), reorder(5).await);
+        });
+    }
+
+    #[test]
+    fn batches() {
+        task::block_on(async {
+            let mut w = ReorderWrite::new(Vec::new(), 2, 3);
+            for part in &[&b"a"[..], b"b", b"c"] {
+                w.write_all(part).await.unwrap();
+            }
+            // the third write releases the first batch
+            assert_eq!(2, w.get_ref().len());
+            w.close().await.unwrap();
+            assert_eq!(3, w.into_inner().len());
+        });
+    }
+}
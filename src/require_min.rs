@@ -0,0 +1,85 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Fail with `UnexpectedEof` if the stream ends before at least `min` bytes have
+    /// been read, to catch truncated messages early. Otherwise, reads are passed through.
+    pub struct RequireMin<R> {
+        #[pin]
+        inner: R,
+        min: u64,
+        read: u64,
+    }
+}
+
+impl<R> RequireMin<R> {
+    pub fn new(inner: R, min: u64) -> Self {
+        RequireMin {
+            inner,
+            min,
+            read: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for RequireMin<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        *this.read += found as u64;
+        if 0 == found && !buf.is_empty() && *this.read < *this.min {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "stream ended after {} bytes, but {} are required",
+                    this.read, this.min
+                ),
+            )));
+        }
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::RequireMin;
+
+    #[test]
+    fn too_short() {
+        task::block_on(async {
+            let mut r = RequireMin::new(io::Cursor::new(b"abc"), 5);
+            let mut out = Vec::new();
+            let err = r.read_to_end(&mut out).await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert_eq!(b"abc", out.as_slice());
+        });
+    }
+
+    #[test]
+    fn long_enough() {
+        task::block_on(async {
+            let mut r = RequireMin::new(io::Cursor::new(b"0123456789"), 5);
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).await.unwrap();
+            assert_eq!(b"0123456789", out.as_slice());
+        });
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Deliver some ranges of the stream twice, like retransmitted TCP segments, to test
+    /// that a sequencing layer above discards duplicates.
+    ///
+    /// Each `(offset, len)` in the `duplicates` is re-delivered, in the order given, as
+    /// soon as all of its bytes have been read, and before anything else is read. Only the
+    /// data which may still be re-delivered is kept.
+    pub struct Retransmit<R> {
+        #[pin]
+        inner: R,
+        duplicates: VecDeque<(u64, usize)>,
+        history: Vec<u8>,
+        // the stream offset of the start of `history`
+        base: u64,
+        sending: Vec<u8>,
+    }
+}
+
+impl<R> Retransmit<R> {
+    pub fn new(inner: R, duplicates: Vec<(u64, usize)>) -> Self {
+        Retransmit {
+            inner,
+            duplicates: duplicates.into(),
+            history: Vec::new(),
+            base: 0,
+            sending: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Retransmit<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let delivered = *this.base + this.history.len() as u64;
+        if let Some(&(offset, len)) = this.duplicates.front() {
+            if this.sending.is_empty() && offset + len as u64 <= delivered {
+                this.duplicates.pop_front();
+                let start = (offset - *this.base) as usize;
+                this.sending
+                    .extend_from_slice(&this.history[start..start + len]);
+            }
+        }
+
+        if !this.sending.is_empty() {
+            let using = this.sending.len().min(buf.len());
+            buf[..using].copy_from_slice(&this.sending[..using]);
+            this.sending.drain(..using);
+            return Poll::Ready(Ok(using));
+        }
+
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        this.history.extend_from_slice(&buf[..found]);
+
+        // forget anything which will not be re-delivered
+        let keep_from = this
+            .duplicates
+            .iter()
+            .map(|&(offset, _)| offset)
+            .min()
+            .unwrap_or(u64::MAX)
+            .max(*this.base);
+        let forget = ((keep_from - *this.base) as usize).min(this.history.len());
+        this.history.drain(..forget);
+        *this.base += forget as u64;
+
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::DequeReader;
+    use crate::Retransmit;
+    use crate::ShortRead;
+
+    #[test]
+    fn dedup() {
+        task::block_on(async {
+            // records of a sequence number, then three bytes of payload
+            let input = b"0abc1def2ghi3jkl";
+            let mut m = DequeReader::new(Retransmit::new(
+                ShortRead::new(io::Cursor::new(input), vec![6, 6, 1, 9].into_iter()),
+                vec![(4, 4), (0, 8), (12, 4)],
+            ));
+            let mut raw = Vec::new();
+            let mut payload = Vec::new();
+            let mut next = b'0';
+            loop {
+                let mut record = [0u8; 4];
+                match m.read_exact(&mut record).await {
+                    Ok(()) => (),
+                    Err(e) if io::ErrorKind::UnexpectedEof == e.kind() => break,
+                    Err(e) => panic!("{:?}", e),
+                }
+                raw.extend_from_slice(&record);
+                if record[0] == next {
+                    payload.extend_from_slice(&record[1..]);
+                    next += 1;
+                }
+            }
+            assert_eq!(&b"0abc1def2ghi1def0abc1def3jkl3jkl"[..], raw.as_slice());
+            assert_eq!(b"abcdefghijkl", payload.as_slice());
+        });
+    }
+}
@@ -0,0 +1,220 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::io::AsyncBufRead as _;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::task::Waker;
+use futures::AsyncRead;
+
+use crate::DequeReader;
+
+struct Shared<R> {
+    reader: DequeReader<R>,
+    max_lead: u64,
+    /// The stream offset of the start of `reader`'s buffer.
+    base: u64,
+    /// The stream offset of each consumer, or `None` once it has been dropped.
+    positions: Vec<Option<u64>>,
+    waiting: Vec<Waker>,
+}
+
+impl<R> Shared<R> {
+    fn wait(&mut self, cx: &Context) {
+        if !self.waiting.iter().any(|w| w.will_wake(cx.waker())) {
+            self.waiting.push(cx.waker().clone());
+        }
+    }
+
+    fn slowest(&self) -> u64 {
+        self.positions
+            .iter()
+            .flatten()
+            .copied()
+            .min()
+            .unwrap_or(self.base)
+    }
+
+    /// Discard data every consumer has read, returning the tasks to wake.
+    fn release(&mut self) -> Vec<Waker>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let slowest = self.slowest();
+        if slowest > self.base {
+            Pin::new(&mut self.reader).consume((slowest - self.base) as usize);
+            self.base = slowest;
+        }
+        std::mem::take(&mut self.waiting)
+    }
+}
+
+/// Share one stream between several consumers, each of which reads all of it.
+///
+/// Data is buffered until every consumer has read it. To stop one consumer from
+/// racing ahead, and the buffer growing without limit, no consumer may get more than
+/// `max_lead` bytes ahead of the slowest; its reads return `Pending` until the others
+/// have caught up.
+///
+/// Consumers which are dropped no longer hold the others back.
+pub struct RoundRobinBuffer<R> {
+    shared: Arc<Mutex<Shared<R>>>,
+}
+
+/// One reader of a `RoundRobinBuffer`, from `RoundRobinBuffer::consumer`.
+pub struct RoundRobinConsumer<R> {
+    shared: Arc<Mutex<Shared<R>>>,
+    id: usize,
+}
+
+impl<R> RoundRobinBuffer<R> {
+    /// # Panics
+    ///
+    /// Panics if `max_lead` is zero.
+    pub fn new(inner: R, max_lead: usize) -> Self {
+        assert!(max_lead > 0, "max_lead must be non-zero");
+        RoundRobinBuffer {
+            shared: Arc::new(Mutex::new(Shared {
+                reader: DequeReader::new(inner),
+                max_lead: max_lead as u64,
+                base: 0,
+                positions: Vec::new(),
+                waiting: Vec::new(),
+            })),
+        }
+    }
+
+    /// Add a consumer, which starts reading at the oldest data still buffered.
+    pub fn consumer(&self) -> RoundRobinConsumer<R> {
+        let mut shared = self.shared.lock().expect("poisoned");
+        let start = shared.slowest();
+        shared.positions.push(Some(start));
+        RoundRobinConsumer {
+            shared: self.shared.clone(),
+            id: shared.positions.len() - 1,
+        }
+    }
+}
+
+impl<R> RoundRobinConsumer<R> {
+    /// The number of bytes of the stream this consumer has read.
+    pub fn consumed(&self) -> u64 {
+        self.shared.lock().expect("poisoned").positions[self.id].expect("live consumer")
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for RoundRobinConsumer<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut shared = self.shared.lock().expect("poisoned");
+        let pos = shared.positions[self.id].expect("live consumer");
+
+        let lead = pos - shared.slowest();
+        if lead >= shared.max_lead {
+            shared.wait(cx);
+            return Poll::Pending;
+        }
+
+        if pos == shared.base + shared.reader.buffer().len() as u64 {
+            // the inner reader only wakes the last task to poll it, so any other task
+            // which is waiting for it is woken when we make progress, below
+            match Pin::new(&mut shared.reader).poll_read_more(cx) {
+                Poll::Pending => {
+                    shared.wait(cx);
+                    return Poll::Pending;
+                }
+                Poll::Ready(Ok(true)) => (),
+                Poll::Ready(Ok(false)) => return Poll::Ready(Ok(0)),
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            }
+        }
+
+        let start = (pos - shared.base) as usize;
+        let available = &shared.reader.buffer()[start..];
+        let using = available
+            .len()
+            .min(buf.len())
+            .min((shared.max_lead - lead) as usize);
+        buf[..using].copy_from_slice(&available[..using]);
+        shared.positions[self.id] = Some(pos + using as u64);
+
+        let wake = shared.release();
+        drop(shared);
+        for waker in wake {
+            waker.wake();
+        }
+        Poll::Ready(Ok(using))
+    }
+}
+
+impl<R> Drop for RoundRobinConsumer<R> {
+    fn drop(&mut self) {
+        let wake = match self.shared.lock() {
+            Ok(mut shared) => {
+                shared.positions[self.id] = None;
+                std::mem::take(&mut shared.waiting)
+            }
+            Err(_) => return,
+        };
+        for waker in wake {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::future::FutureExt as _;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::RoundRobinBuffer;
+
+    #[test]
+    fn fair() {
+        task::block_on(async {
+            let data: Vec<u8> = (0..20u8).collect();
+            let rr = RoundRobinBuffer::new(io::Cursor::new(data.clone()), 4);
+            let mut a = rr.consumer();
+            let mut b = rr.consumer();
+            let mut buf = [0u8; 64];
+
+            assert_eq!(4, a.read(&mut buf).await.unwrap());
+            assert_eq!(&data[..4], &buf[..4]);
+            // a is as far ahead as it's allowed to get
+            assert!(a.read(&mut buf).now_or_never().is_none());
+
+            assert_eq!(3, b.read(&mut buf[..3]).await.unwrap());
+            assert_eq!(3, a.read(&mut buf).await.unwrap());
+            assert_eq!(&data[4..7], &buf[..3]);
+            assert_eq!(7, a.consumed());
+            assert_eq!(3, b.consumed());
+
+            let mut out_a = Vec::new();
+            let mut out_b = Vec::new();
+            let (ra, rb) = futures::join!(a.read_to_end(&mut out_a), b.read_to_end(&mut out_b));
+            assert_eq!(13, ra.unwrap());
+            assert_eq!(17, rb.unwrap());
+            assert_eq!(&data[7..], out_a.as_slice());
+            assert_eq!(&data[3..], out_b.as_slice());
+        });
+    }
+
+    #[test]
+    fn dropped_consumer() {
+        task::block_on(async {
+            let rr = RoundRobinBuffer::new(io::Cursor::new(b"hello world"), 2);
+            let mut a = rr.consumer();
+            drop(rr.consumer());
+            let mut out = Vec::new();
+            a.read_to_end(&mut out).await.unwrap();
+            assert_eq!(b"hello world", out.as_slice());
+        });
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+
+/// One step of a `Scripted` reader's script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadEvent {
+    /// Return this data. If it doesn't fit in the caller's buffer, the rest is returned
+    /// by the following reads.
+    Data(Vec<u8>),
+    /// Return `Poll::Pending`, with an immediate wakeup.
+    Pending,
+    /// Return a zero-length read.
+    Eof,
+    /// Fail the read with an error of this kind.
+    Error(io::ErrorKind),
+}
+
+/// A reader which plays back a script of `ReadEvent`s, one per read, for deterministic tests.
+///
+/// Once the script is exhausted, every read returns EOF.
+///
+/// ```rust
+/// use futures::io;
+/// use futures::io::AsyncReadExt as _;
+/// use aiowrap::{ReadEvent, Scripted};
+/// # async_std::task::block_on(async {
+/// let mut r = Scripted::new(vec![
+///     ReadEvent::Data(b"hello".to_vec()),
+///     ReadEvent::Pending,
+///     ReadEvent::Data(b" world".to_vec()),
+///     ReadEvent::Error(io::ErrorKind::ConnectionReset),
+/// ]);
+/// let mut out = Vec::new();
+/// let err = r.read_to_end(&mut out).await.unwrap_err();
+/// assert_eq!(io::ErrorKind::ConnectionReset, err.kind());
+/// assert_eq!(b"hello world", out.as_slice());
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scripted {
+    script: VecDeque<ReadEvent>,
+}
+
+impl Scripted {
+    pub fn new(script: Vec<ReadEvent>) -> Self {
+        Scripted {
+            script: script.into(),
+        }
+    }
+
+    /// The events which have not been played yet.
+    pub fn remaining(&self) -> impl Iterator<Item = &ReadEvent> {
+        self.script.iter()
+    }
+}
+
+impl AsyncRead for Scripted {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.script.pop_front() {
+            Some(ReadEvent::Data(mut data)) => {
+                let using = data.len().min(buf.len());
+                buf[..using].copy_from_slice(&data[..using]);
+                if using < data.len() {
+                    data.drain(..using);
+                    self.script.push_front(ReadEvent::Data(data));
+                }
+                Poll::Ready(Ok(using))
+            }
+            Some(ReadEvent::Pending) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Some(ReadEvent::Eof) | None => Poll::Ready(Ok(0)),
+            Some(ReadEvent::Error(kind)) => {
+                Poll::Ready(Err(io::Error::new(kind, "scripted error")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+
+    use futures::io;
+    use futures::task::noop_waker;
+    use futures::task::Context;
+    use futures::task::Poll;
+    use futures::AsyncRead as _;
+
+    use crate::ReadEvent;
+    use crate::Scripted;
+
+    #[test]
+    fn script() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut r = Scripted::new(vec![
+            ReadEvent::Data(b"hello".to_vec()),
+            ReadEvent::Pending,
+            ReadEvent::Error(io::ErrorKind::ConnectionReset),
+            ReadEvent::Eof,
+            ReadEvent::Data(b"!".to_vec()),
+        ]);
+        let mut buf = [0u8; 3];
+        let mut read = |r: &mut Scripted| match Pin::new(r).poll_read(&mut cx, &mut buf) {
+            Poll::Ready(Ok(n)) => Poll::Ready(Ok(buf[..n].to_vec())),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e.kind())),
+            Poll::Pending => Poll::Pending,
+        };
+
+        assert_eq!(Poll::Ready(Ok(b"hel".to_vec())), read(&mut r));
+        assert_eq!(Poll::Ready(Ok(b"lo".to_vec())), read(&mut r));
+        assert_eq!(Poll::Pending, read(&mut r));
+        assert_eq!(
+            Poll::Ready(Err(io::ErrorKind::ConnectionReset)),
+            read(&mut r)
+        );
+        assert_eq!(Poll::Ready(Ok(Vec::new())), read(&mut r));
+        assert_eq!(Poll::Ready(Ok(b"!".to_vec())), read(&mut r));
+        assert_eq!(Poll::Ready(Ok(Vec::new())), read(&mut r));
+        assert_eq!(0, r.remaining().count());
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::io;
 
 use futures::task::Context;
@@ -17,6 +18,9 @@ pin_project! {
     /// Currently, no effort is made to make reads longer, if the underlying
     /// reader naturally returns short reads.
     ///
+    /// The sizes actually produced can be recorded, see `recording`, so a run
+    /// with a randomised `decider` can be replayed exactly.
+    ///
     /// # Examples
     ///
     /// Short read:
@@ -40,6 +44,8 @@ pin_project! {
         #[pin]
         inner: R,
         decider: I,
+        produced: Option<Vec<usize>>,
+        label: Option<&'static str>,
     }
 }
 
@@ -52,6 +58,9 @@ impl<R: AsyncRead, I: Iterator<Item = usize>> AsyncRead for ShortRead<R, I> {
         let this = self.project();
         let wanted = match this.decider.next() {
             Some(0) => {
+                if let Some(produced) = this.produced {
+                    produced.push(0);
+                }
                 cx.waker().wake_by_ref();
                 return Poll::Pending;
             }
@@ -61,13 +70,57 @@ impl<R: AsyncRead, I: Iterator<Item = usize>> AsyncRead for ShortRead<R, I> {
         let wanted = wanted.min(buf.len());
 
         let buf = &mut buf[..wanted];
-        this.inner.poll_read(cx, buf)
+        let result = this.inner.poll_read(cx, buf);
+        if let (Poll::Ready(Ok(n)), Some(produced)) = (&result, this.produced) {
+            if 0 != *n {
+                produced.push(*n);
+            }
+        }
+        result
+    }
+}
+
+impl<R: fmt::Debug, I> fmt::Debug for ShortRead<R, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShortRead")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("produced", &self.produced.as_ref().map(Vec::len))
+            .finish()
     }
 }
 
 impl<R, I: Iterator<Item = usize>> ShortRead<R, I> {
     pub fn new(inner: R, decider: I) -> Self {
-        ShortRead { inner, decider }
+        ShortRead {
+            inner,
+            decider,
+            produced: None,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Record the sizes of the reads produced, for `produced_sizes`. Disabled by default.
+    pub fn recording(mut self) -> Self {
+        self.produced = Some(Vec::new());
+        self
+    }
+
+    /// The sizes of the reads produced so far, with injected `Pending`s recorded as `0`,
+    /// or nothing, if not `recording`.
+    ///
+    /// Feeding these back in as a `decider` reproduces the same sequence of reads,
+    /// given the same underlying data. If the inner reader itself returns `Pending`, the
+    /// size taken from the `decider` for that read is dropped, and not recorded, so replays
+    /// are only exact over readers which never return `Pending`.
+    pub fn produced_sizes(&self) -> &[usize] {
+        self.produced.as_deref().unwrap_or_default()
     }
 
     pub fn into_inner(self) -> R {
@@ -81,6 +134,7 @@ mod tests {
 
     use async_std::task;
     use futures::io;
+    use futures::io::AsyncRead;
     use futures::io::AsyncReadExt as _;
 
     #[test]
@@ -116,4 +170,49 @@ mod tests {
             assert_eq!(1, interrupting.read(&mut buf).await.unwrap());
         });
     }
+
+    async fn chunks<R: AsyncRead + Unpin>(mut r: R) -> Vec<Vec<u8>> {
+        let mut chunks = Vec::new();
+        let mut buf = [0u8; 16];
+        loop {
+            let n = r.read(&mut buf).await.unwrap();
+            if 0 == n {
+                return chunks;
+            }
+            chunks.push(buf[..n].to_vec());
+        }
+    }
+
+    #[test]
+    fn replay() {
+        task::block_on(async {
+            let data = b"the quick brown fox jumps over the lazy dog";
+            let mut naughty = ShortRead::new(
+                io::Cursor::new(&data[..]),
+                (0..).map(|i: usize| i.wrapping_mul(7919) % 6),
+            )
+            .recording();
+            let expected = chunks(&mut naughty).await;
+            assert_eq!(&data[..], expected.concat().as_slice());
+            assert!(naughty.produced_sizes().contains(&0));
+
+            let replayed = ShortRead::new(
+                io::Cursor::new(&data[..]),
+                naughty.produced_sizes().to_vec().into_iter(),
+            );
+            assert_eq!(expected, chunks(replayed).await);
+        });
+    }
+
+    #[test]
+    fn not_recording() {
+        task::block_on(async {
+            let mut naughty = ShortRead::new(io::Cursor::new(b"abc"), vec![1, 0, 2].into_iter());
+            assert_eq!(
+                vec![b"a".to_vec(), b"bc".to_vec()],
+                chunks(&mut naughty).await
+            );
+            assert!(naughty.produced_sizes().is_empty());
+        });
+    }
 }
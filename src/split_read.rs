@@ -0,0 +1,117 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Split the stream at exact offsets, to reproduce bugs with data split across reads,
+    /// such as a header split between two packets.
+    ///
+    /// No read crosses any of the `splits`, which are offsets from the start of the stream,
+    /// in increasing order. After each split point is reached, the next read returns
+    /// `Poll::Pending`, with an immediate wakeup, so the data before the split is seen
+    /// on its own. Reads are otherwise passed through unchanged.
+    pub struct SplitRead<R> {
+        #[pin]
+        inner: R,
+        splits: Vec<u64>,
+        next: usize,
+        pos: u64,
+        paused: bool,
+    }
+}
+
+impl<R> SplitRead<R> {
+    /// # Panics
+    ///
+    /// Panics if the `splits` are not in increasing order.
+    pub fn new(inner: R, splits: Vec<u64>) -> Self {
+        assert!(
+            splits.windows(2).all(|w| w[0] < w[1]),
+            "splits must be increasing"
+        );
+        SplitRead {
+            inner,
+            splits,
+            next: 0,
+            pos: 0,
+            paused: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for SplitRead<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if *this.paused {
+            *this.paused = false;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let wanted = match this.splits.get(*this.next) {
+            Some(&split) => buf.len().min((split - *this.pos) as usize),
+            None => buf.len(),
+        };
+        let result = this.inner.poll_read(cx, &mut buf[..wanted]);
+        if let Poll::Ready(Ok(n)) = result {
+            *this.pos += n as u64;
+            if this.splits.get(*this.next) == Some(this.pos) {
+                *this.next += 1;
+                *this.paused = true;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+    use futures::task::noop_waker_ref;
+    use futures::task::Context;
+    use futures::task::Poll;
+    use futures::AsyncRead;
+
+    use crate::DequeReader;
+    use crate::SplitRead;
+
+    #[test]
+    fn split_length_field() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut r = SplitRead::new(io::Cursor::new(b"\x00\x03abc"), vec![1]);
+        let mut buf = [0u8; 8];
+        match Pin::new(&mut r).poll_read(&mut cx, &mut buf) {
+            Poll::Ready(Ok(1)) => (),
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert!(Pin::new(&mut r).poll_read(&mut cx, &mut buf).is_pending());
+        match Pin::new(&mut r).poll_read(&mut cx, &mut buf) {
+            Poll::Ready(Ok(4)) => assert_eq!(b"\x03abc", &buf[..4]),
+            other => panic!("unexpected: {:?}", other),
+        }
+
+        task::block_on(async {
+            let mut m = DequeReader::new(SplitRead::new(io::Cursor::new(b"\x00\x03abc"), vec![1]));
+            let len = m.read_u16_be().await.unwrap();
+            let mut body = vec![0u8; usize::from(len)];
+            m.read_exact(&mut body).await.unwrap();
+            assert_eq!(b"abc", body.as_slice());
+        });
+    }
+}
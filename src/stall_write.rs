@@ -0,0 +1,164 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncWrite;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Intentionally stall writes, flushes and closes, to test `AsyncWrite` code.
+    ///
+    /// Each operation has its own decider, consulted once per poll:
+    ///
+    ///  * `write` decides how much of each write is accepted, like `ShortRead`.
+    ///    A length of 0 generates a `Poll::Pending`, with an immediate wakeup.
+    ///  * `flush` and `close` generate a `Poll::Pending`, with an immediate wakeup,
+    ///    when they return `true`.
+    ///
+    /// When a decider runs out, the operation is passed through to the inner writer
+    /// unchanged.
+    ///
+    /// ```rust
+    /// use std::iter;
+    /// use futures::io::AsyncWriteExt as _;
+    /// # async_std::task::block_on(async {
+    /// let mut w = aiowrap::StallWrite::new(
+    ///     Vec::new(),
+    ///     vec![2].into_iter(),
+    ///     iter::empty(),
+    ///     vec![true, true].into_iter(),
+    /// );
+    /// assert_eq!(2, w.write(b"hello").await.unwrap());
+    /// w.close().await.unwrap();
+    /// assert_eq!(2, w.stalls());
+    /// # });
+    /// ```
+    pub struct StallWrite<W, I, F, C> {
+        #[pin]
+        inner: W,
+        write: I,
+        flush: F,
+        close: C,
+        stalls: usize,
+    }
+}
+
+impl<W, I, F, C> StallWrite<W, I, F, C>
+where
+    I: Iterator<Item = usize>,
+    F: Iterator<Item = bool>,
+    C: Iterator<Item = bool>,
+{
+    pub fn new(inner: W, write: I, flush: F, close: C) -> Self {
+        StallWrite {
+            inner,
+            write,
+            flush,
+            close,
+            stalls: 0,
+        }
+    }
+
+    /// The number of times a `Pending` has been generated.
+    pub fn stalls(&self) -> usize {
+        self.stalls
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+fn stall<T>(cx: &mut Context<'_>, stalls: &mut usize) -> Poll<T> {
+    *stalls += 1;
+    cx.waker().wake_by_ref();
+    Poll::Pending
+}
+
+impl<W, I, F, C> AsyncWrite for StallWrite<W, I, F, C>
+where
+    W: AsyncWrite,
+    I: Iterator<Item = usize>,
+    F: Iterator<Item = bool>,
+    C: Iterator<Item = bool>,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let wanted = match this.write.next() {
+            Some(0) => return stall(cx, this.stalls),
+            Some(wanted) => wanted.min(buf.len()),
+            None => buf.len(),
+        };
+        this.inner.poll_write(cx, &buf[..wanted])
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        if let Some(true) = this.flush.next() {
+            return stall(cx, this.stalls);
+        }
+        this.inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        if let Some(true) = this.close.next() {
+            return stall(cx, this.stalls);
+        }
+        this.inner.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use async_std::task;
+    use futures::io::AsyncWriteExt as _;
+
+    use crate::StallWrite;
+
+    #[test]
+    fn write_all() {
+        task::block_on(async {
+            let mut w = StallWrite::new(
+                Vec::new(),
+                vec![0, 3, 0, 0, 1].into_iter(),
+                iter::empty(),
+                iter::empty(),
+            );
+            w.write_all(b"hello world").await.unwrap();
+            assert_eq!(3, w.stalls());
+            assert_eq!(b"hello world", w.get_ref().as_slice());
+        });
+    }
+
+    #[test]
+    fn flush_and_close() {
+        task::block_on(async {
+            let mut w = StallWrite::new(
+                Vec::new(),
+                iter::empty(),
+                vec![true, false, true].into_iter(),
+                vec![true; 3].into_iter(),
+            );
+            w.write_all(b"hi").await.unwrap();
+            w.flush().await.unwrap();
+            assert_eq!(1, w.stalls());
+            w.flush().await.unwrap();
+            assert_eq!(2, w.stalls());
+            w.close().await.unwrap();
+            assert_eq!(5, w.stalls());
+            assert_eq!(b"hi", w.into_inner().as_slice());
+        });
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncBufRead;
+use futures::AsyncRead;
+use futures::Stream;
+use pin_project_lite::pin_project;
+
+use crate::label;
+use crate::DequeReader;
+
+/// What a `FrameState` found in the buffer.
+#[derive(Debug)]
+pub enum FrameOutcome {
+    /// The buffer does not contain a complete frame yet; read more.
+    NeedMore,
+    /// The first `len` bytes of the buffer are a frame.
+    Frame { len: usize },
+    /// The data is invalid; the stream fails with this error.
+    Error(io::Error),
+}
+
+/// Framing logic for `StatefulFramed`.
+pub trait FrameState {
+    /// Examine the buffered data, which always starts at the start of a frame.
+    ///
+    /// If the answer is `NeedMore`, this will be called again, with the same data, plus
+    /// more, so any progress can be remembered. After returning `Frame`, the next call is
+    /// for the next frame, so any state should be reset.
+    ///
+    /// # Panics
+    ///
+    /// `StatefulFramed` panics if this returns a `Frame` which is empty, or longer than `buf`.
+    fn feed(&mut self, buf: &[u8]) -> FrameOutcome;
+}
+
+pin_project! {
+    /// A `Stream` of the frames from a reader, as found by a user-supplied `FrameState`.
+    ///
+    /// The stream ends after the first error. If the inner reader ends in the middle of
+    /// a frame, the last item is an `UnexpectedEof` error.
+    pub struct StatefulFramed<R, S> {
+        #[pin]
+        inner: DequeReader<R>,
+        state: S,
+        done: bool,
+        label: Option<&'static str>,
+    }
+}
+
+impl<R, S: FrameState> StatefulFramed<R, S> {
+    pub fn new(inner: R, state: S) -> Self {
+        StatefulFramed {
+            inner: DequeReader::new(inner),
+            state,
+            done: false,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// generated by this stream.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn get_state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn get_state_mut(&mut self) -> &mut S {
+        &mut self.state
+    }
+
+    /// Note that any buffered data is lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: fmt::Debug, S: fmt::Debug> fmt::Debug for StatefulFramed<R, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatefulFramed")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("state", &self.state)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead, S: FrameState> Stream for StatefulFramed<R, S> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            let buf = this.inner.buffer();
+            if !buf.is_empty() {
+                match this.state.feed(buf) {
+                    FrameOutcome::NeedMore => (),
+                    FrameOutcome::Frame { len } => {
+                        assert!(
+                            len > 0 && len <= buf.len(),
+                            "frames must be non-empty, and buffered"
+                        );
+                        let frame = buf[..len].to_vec();
+                        this.inner.as_mut().consume(len);
+                        return Poll::Ready(Some(Ok(frame)));
+                    }
+                    FrameOutcome::Error(e) => {
+                        *this.done = true;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+            }
+
+            match ready!(this.inner.as_mut().poll_read_more(cx)) {
+                Ok(true) => (),
+                Ok(false) => {
+                    *this.done = true;
+                    if this.inner.buffer().is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(Err(label::error(
+                        *this.label,
+                        io::ErrorKind::UnexpectedEof,
+                        "stream ended inside a frame",
+                    ))));
+                }
+                Err(e) => {
+                    *this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::stream::StreamExt as _;
+
+    use crate::FrameOutcome;
+    use crate::FrameState;
+    use crate::ShortRead;
+    use crate::StatefulFramed;
+
+    /// Finds COBS-encoded frames, each terminated by a zero byte, checking their structure.
+    #[derive(Default)]
+    struct Cobs {
+        searched: usize,
+    }
+
+    impl FrameState for Cobs {
+        fn feed(&mut self, buf: &[u8]) -> FrameOutcome {
+            let end = match buf[self.searched..].iter().position(|&b| 0 == b) {
+                Some(end) => self.searched + end,
+                None => {
+                    self.searched = buf.len();
+                    return FrameOutcome::NeedMore;
+                }
+            };
+            self.searched = 0;
+
+            // each code byte gives the distance to the next code byte, or the delimiter
+            let mut code = 0;
+            while code < end {
+                code += usize::from(buf[code]);
+            }
+            if code != end {
+                return FrameOutcome::Error(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "code overruns frame",
+                ));
+            }
+            FrameOutcome::Frame { len: end + 1 }
+        }
+    }
+
+    fn decode(frame: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut pos = 0;
+        while frame[pos] != 0 {
+            let code = usize::from(frame[pos]);
+            out.extend_from_slice(&frame[pos + 1..pos + code]);
+            pos += code;
+            if code < 0xff && frame[pos] != 0 {
+                out.push(0);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn cobs() {
+        task::block_on(async {
+            let encoded: &[u8] = b"\x03ab\x02c\x00\x01\x01\x00\x04xyz\x00";
+            let frames = StatefulFramed::new(
+                ShortRead::new(io::Cursor::new(encoded), vec![2, 0, 3, 1, 4, 9].into_iter()),
+                Cobs::default(),
+            );
+            let frames: Vec<Vec<u8>> = frames.map(|frame| decode(&frame.unwrap())).collect().await;
+            assert_eq!(
+                vec![b"ab\0c".to_vec(), b"\0".to_vec(), b"xyz".to_vec()],
+                frames
+            );
+        });
+    }
+
+    #[test]
+    fn cobs_errors() {
+        task::block_on(async {
+            let mut frames =
+                StatefulFramed::new(io::Cursor::new(b"\x05ab\x00\x01\x00"), Cobs::default());
+            let err = frames.next().await.unwrap().unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert!(frames.next().await.is_none());
+
+            let mut frames =
+                StatefulFramed::new(io::Cursor::new(b"\x01\x00\x02a"), Cobs::default());
+            assert_eq!(
+                b"\x01\x00",
+                frames.next().await.unwrap().unwrap().as_slice()
+            );
+            let err = frames.next().await.unwrap().unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert!(frames.next().await.is_none());
+        });
+    }
+
+    /// Claims more than it was given.
+    struct Greedy;
+
+    impl FrameState for Greedy {
+        fn feed(&mut self, buf: &[u8]) -> FrameOutcome {
+            FrameOutcome::Frame { len: buf.len() + 1 }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "frames must be non-empty, and buffered")]
+    fn overlong_frame() {
+        task::block_on(async {
+            let mut frames = StatefulFramed::new(io::Cursor::new(b"abc"), Greedy);
+            let _ = frames.next().await;
+        });
+    }
+}
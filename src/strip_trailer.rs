@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::label;
+
+pin_project! {
+    /// Hold back the last `len` bytes of the stream, a fixed-size trailer, such as a
+    /// signature, so only the body is read. The trailer is available from `trailer`
+    /// once the end of the stream has been read.
+    ///
+    /// Data is only returned once it is known not to be part of the trailer, so reads may
+    /// wait for up to `len` more bytes than they return. If the stream is shorter than
+    /// the trailer, the read at the end fails with `UnexpectedEof`.
+    pub struct StripTrailer<R> {
+        #[pin]
+        inner: R,
+        len: usize,
+        held: Vec<u8>,
+        done: bool,
+        label: Option<&'static str>,
+    }
+}
+
+impl<R> StripTrailer<R> {
+    pub fn new(inner: R, len: usize) -> Self {
+        StripTrailer {
+            inner,
+            len,
+            held: Vec::with_capacity(len),
+            done: false,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// generated by this reader.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// The trailer, once the end of the stream has been read.
+    pub fn trailer(&self) -> Option<&[u8]> {
+        if self.done {
+            Some(&self.held)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for StripTrailer<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StripTrailer")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .field("held", &self.held.len())
+            .finish()
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for StripTrailer<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut this = self.project();
+        if *this.done || buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            let found = ready!(this.inner.as_mut().poll_read(cx, buf))?;
+            if 0 == found {
+                if this.held.len() < *this.len {
+                    return Poll::Ready(Err(label::error(
+                        *this.label,
+                        io::ErrorKind::UnexpectedEof,
+                        "stream ended inside the trailer",
+                    )));
+                }
+                *this.done = true;
+                return Poll::Ready(Ok(0));
+            }
+
+            // never more than we just read, as at most `len` bytes were held
+            this.held.extend_from_slice(&buf[..found]);
+            let release = this.held.len().saturating_sub(*this.len);
+            if release > 0 {
+                buf[..release].copy_from_slice(&this.held[..release]);
+                this.held.drain(..release);
+                return Poll::Ready(Ok(release));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::ShortRead;
+    use crate::StripTrailer;
+
+    #[test]
+    fn stripped() {
+        task::block_on(async {
+            let mut r = StripTrailer::new(
+                ShortRead::new(
+                    io::Cursor::new(b"body data\xde\xad\xbe\xef"),
+                    vec![2, 0, 3, 1, 5, 9].into_iter(),
+                ),
+                4,
+            );
+            let mut body = Vec::new();
+            assert!(r.trailer().is_none());
+            r.read_to_end(&mut body).await.unwrap();
+            assert_eq!(b"body data", body.as_slice());
+            assert_eq!(Some(&b"\xde\xad\xbe\xef"[..]), r.trailer());
+
+            let mut r = StripTrailer::new(io::Cursor::new(b"abc"), 4).with_label("signed");
+            let err = r.read_to_end(&mut body).await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert_eq!("signed: stream ended inside the trailer", err.to_string());
+        });
+    }
+}
@@ -0,0 +1,112 @@
+use std::hash::Hasher;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Feed every byte read from the inner reader to a `Hasher`, e.g. to check a digest
+    /// once the stream has been parsed. Reads are passed through unchanged.
+    ///
+    /// Wrap a `DequeReader` in this, rather than the other way around, so only the bytes
+    /// the caller actually reads are hashed, however the `DequeReader` reads them.
+    pub struct TeeHash<R, H> {
+        #[pin]
+        inner: R,
+        hasher: H,
+    }
+}
+
+impl<R, H: Hasher> TeeHash<R, H> {
+    pub fn new(inner: R, hasher: H) -> Self {
+        TeeHash { inner, hasher }
+    }
+
+    /// The hash of everything read so far.
+    pub fn finalize(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    pub fn into_inner(self) -> (R, H) {
+        (self.inner, self.hasher)
+    }
+}
+
+impl<R: AsyncRead, H: Hasher> AsyncRead for TeeHash<R, H> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        this.hasher.write(&buf[..found]);
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use std::iter;
+
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::DequeReader;
+    use crate::ShortRead;
+    use crate::TeeHash;
+
+    /// FNV-1a, which hashes the same however the data is split between writes.
+    struct Fnv(u64);
+
+    impl Default for Fnv {
+        fn default() -> Self {
+            Fnv(0xcbf2_9ce4_8422_2325)
+        }
+    }
+
+    impl Hasher for Fnv {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = (self.0 ^ u64::from(b)).wrapping_mul(0x100_0000_01b3);
+            }
+        }
+    }
+
+    #[test]
+    fn hashes_everything_read() {
+        task::block_on(async {
+            let input: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+            let mut expected = Fnv::default();
+            expected.write(&input);
+
+            let mut r = TeeHash::new(
+                DequeReader::new(ShortRead::new(
+                    io::Cursor::new(input.clone()),
+                    [3, 0, 9000, 5].iter().copied().chain(iter::repeat(12_000)),
+                )),
+                Fnv::default(),
+            );
+            let mut small = [0u8; 2];
+            r.read_exact(&mut small).await.unwrap();
+            // large enough to bypass the buffer
+            let mut large = vec![0u8; 10_000];
+            r.read_exact(&mut large).await.unwrap();
+            let mut rest = Vec::new();
+            r.read_to_end(&mut rest).await.unwrap();
+
+            assert_eq!(input.len(), 2 + large.len() + rest.len());
+            assert_eq!(expected.finish(), r.finalize());
+        });
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::timer::Deadline;
+use crate::Timer;
+
+/// The data returned by each read from a stream, and when, as captured by `TimelineRecorder`,
+/// for replaying with `TimelineReplay`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    reads: Vec<(Duration, Vec<u8>)>,
+}
+
+impl Timeline {
+    /// Each read, with its time since the recorder was first polled.
+    pub fn reads(&self) -> &[(Duration, Vec<u8>)] {
+        &self.reads
+    }
+
+    /// Serialise, as, for each read, the time in nanoseconds, as a big-endian `u64`, then
+    /// the length, as a big-endian `u32`, then the data.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (at, data) in &self.reads {
+            out.extend_from_slice(&(at.as_nanos() as u64).to_be_bytes());
+            out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            out.extend_from_slice(data);
+        }
+        out
+    }
+
+    /// Parse the output of `to_bytes`, failing with `InvalidData` if it is truncated.
+    pub fn from_bytes(mut bytes: &[u8]) -> io::Result<Timeline> {
+        let truncated = || io::Error::new(io::ErrorKind::InvalidData, "truncated timeline");
+        let mut reads = Vec::new();
+        while !bytes.is_empty() {
+            if bytes.len() < 12 {
+                return Err(truncated());
+            }
+            let at = u64::from_be_bytes(bytes[..8].try_into().expect("sliced"));
+            let len = u32::from_be_bytes(bytes[8..12].try_into().expect("sliced")) as usize;
+            bytes = &bytes[12..];
+            if bytes.len() < len {
+                return Err(truncated());
+            }
+            reads.push((Duration::from_nanos(at), bytes[..len].to_vec()));
+            bytes = &bytes[len..];
+        }
+        Ok(Timeline { reads })
+    }
+}
+
+pin_project! {
+    /// Record the data returned by each read from the inner reader, and when, into a
+    /// `Timeline`, to replay timing-sensitive sessions. Reads are passed through unchanged.
+    ///
+    /// Times are measured from when the reader is first polled.
+    pub struct TimelineRecorder<R> {
+        #[pin]
+        inner: R,
+        start: Option<Instant>,
+        timeline: Timeline,
+    }
+}
+
+impl<R> TimelineRecorder<R> {
+    pub fn new(inner: R) -> Self {
+        TimelineRecorder {
+            inner,
+            start: None,
+            timeline: Timeline::default(),
+        }
+    }
+
+    /// Every read so far.
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for TimelineRecorder<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let start = *this.start.get_or_insert_with(Instant::now);
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        if found > 0 {
+            this.timeline
+                .reads
+                .push((start.elapsed(), buf[..found].to_vec()));
+        }
+        Poll::Ready(Ok(found))
+    }
+}
+
+/// A reader which replays a `Timeline`, returning each read's data no sooner, after
+/// the previous read, than it was originally returned. Then, every read returns EOF.
+///
+/// The time starts when the reader is first polled. If data doesn't fit in the caller's
+/// buffer, the rest is returned by the following reads, without any delay.
+pub struct TimelineReplay {
+    reads: VecDeque<(Duration, Vec<u8>)>,
+    previous: Duration,
+    deadline: Option<Deadline>,
+    timer: Option<Arc<dyn Timer>>,
+}
+
+impl TimelineReplay {
+    pub fn new(timeline: Timeline) -> Self {
+        TimelineReplay {
+            reads: timeline.reads.into(),
+            previous: Duration::from_secs(0),
+            deadline: None,
+            timer: None,
+        }
+    }
+
+    /// Use this `Timer` for the delays, instead of the default `ThreadTimer`.
+    pub fn set_timer(&mut self, timer: Arc<dyn Timer>) {
+        self.timer = Some(timer);
+    }
+}
+
+impl AsyncRead for TimelineReplay {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let (at, mut data) = match this.reads.pop_front() {
+            Some(read) => read,
+            None => return Poll::Ready(Ok(0)),
+        };
+
+        let delay = at.saturating_sub(this.previous);
+        if delay > Duration::from_secs(0) {
+            let timer = this.timer.as_ref();
+            let deadline = this
+                .deadline
+                .get_or_insert_with(|| Deadline::after(timer, delay));
+            if deadline.poll(cx).is_pending() {
+                this.reads.push_front((at, data));
+                return Poll::Pending;
+            }
+            this.deadline = None;
+            this.previous = at;
+        }
+
+        let using = data.len().min(buf.len());
+        buf[..using].copy_from_slice(&data[..using]);
+        if using < data.len() {
+            data.drain(..using);
+            this.reads.push_front((at, data));
+        }
+        Poll::Ready(Ok(using))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::DelayFirst;
+    use crate::ShortRead;
+    use crate::Timeline;
+    use crate::TimelineRecorder;
+    use crate::TimelineReplay;
+
+    #[test]
+    fn capture_replay() {
+        task::block_on(async {
+            let delay = Duration::from_millis(20);
+            let mut r = TimelineRecorder::new(DelayFirst::new(
+                ShortRead::new(
+                    io::Cursor::new(b"hello, world"),
+                    vec![5, 0, 2, 9].into_iter(),
+                ),
+                delay,
+            ));
+            let mut original = Vec::new();
+            r.read_to_end(&mut original).await.unwrap();
+
+            let timeline = r.timeline().clone();
+            assert_eq!(3, timeline.reads().len());
+            assert!(timeline.reads()[0].0 >= delay);
+            let bytes = timeline.to_bytes();
+            assert_eq!(timeline, Timeline::from_bytes(&bytes).unwrap());
+            let err = Timeline::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+            let start = Instant::now();
+            let mut replay = TimelineReplay::new(Timeline::from_bytes(&bytes).unwrap());
+            let mut replayed = Vec::new();
+            let mut buf = [0u8; 3];
+            loop {
+                let found = replay.read(&mut buf).await.unwrap();
+                if 0 == found {
+                    break;
+                }
+                replayed.extend_from_slice(&buf[..found]);
+            }
+            assert!(start.elapsed() >= delay);
+            assert_eq!(original, replayed);
+        });
+    }
+}
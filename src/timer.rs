@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::task::Waker;
+
+/// A future which completes after some time, as produced by a `Timer`.
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A source of timers, for the wrappers which wait, so they can use any runtime's timers.
+///
+/// By default, `ThreadTimer` is used, which depends on no runtime at all.
+///
+/// A `Timer` which never waits, e.g. to run timeouts instantly in tests; one for a runtime
+/// would call its sleep function, such as `Box::pin(tokio::time::sleep(duration))`.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use aiowrap::{Sleep, Timer};
+///
+/// struct NoWait;
+///
+/// impl Timer for NoWait {
+///     fn sleep(&self, _duration: Duration) -> Sleep {
+///         Box::pin(futures::future::ready(()))
+///     }
+/// }
+///
+/// # async_std::task::block_on(async {
+/// NoWait.sleep(Duration::from_secs(3600)).await;
+/// # });
+/// ```
+pub trait Timer: Send + Sync {
+    /// A future which completes once `duration` has passed. The time starts now,
+    /// not when the future is first polled.
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// The default `Timer`, which needs no runtime.
+///
+/// All sleeps share one background thread, which is started when a sleep is first polled
+/// before it has completed, and which wakes each polling task at its deadline. Dropping a
+/// sleep cancels it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadTimer;
+
+impl Timer for ThreadTimer {
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(ThreadSleep {
+            at: Instant::now() + duration,
+            id: None,
+        })
+    }
+}
+
+/// The sleeps waiting for the timer thread, by deadline, then registration order.
+struct Sleepers {
+    waiting: BTreeMap<(Instant, u64), Waker>,
+    next_id: u64,
+    running: bool,
+}
+
+static SLEEPERS: Mutex<Sleepers> = Mutex::new(Sleepers {
+    waiting: BTreeMap::new(),
+    next_id: 0,
+    running: false,
+});
+
+static CHANGED: Condvar = Condvar::new();
+
+/// The timer thread: wake each sleeper once its deadline has passed.
+fn run_timer() {
+    let mut sleepers = SLEEPERS.lock().expect("poisoned");
+    loop {
+        let now = Instant::now();
+        let later = sleepers.waiting.split_off(&(now, u64::MAX));
+        let due = std::mem::replace(&mut sleepers.waiting, later);
+        if !due.is_empty() {
+            drop(sleepers);
+            due.into_iter().for_each(|(_, waker)| waker.wake());
+            sleepers = SLEEPERS.lock().expect("poisoned");
+            continue;
+        }
+
+        sleepers = match sleepers.waiting.keys().next() {
+            Some(&(at, _)) => {
+                CHANGED
+                    .wait_timeout(sleepers, at - now)
+                    .expect("poisoned")
+                    .0
+            }
+            None => CHANGED.wait(sleepers).expect("poisoned"),
+        };
+    }
+}
+
+struct ThreadSleep {
+    at: Instant,
+    /// Set once registered with the timer thread.
+    id: Option<u64>,
+}
+
+impl ThreadSleep {
+    fn cancel(&mut self) {
+        if let Some(id) = self.id.take() {
+            if let Ok(mut sleepers) = SLEEPERS.lock() {
+                sleepers.waiting.remove(&(self.at, id));
+            }
+        }
+    }
+}
+
+impl Future for ThreadSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if Instant::now() >= self.at {
+            self.cancel();
+            return Poll::Ready(());
+        }
+
+        let mut sleepers = SLEEPERS.lock().expect("poisoned");
+        let id = match self.id {
+            Some(id) => id,
+            None => {
+                sleepers.next_id += 1;
+                sleepers.next_id
+            }
+        };
+        sleepers.waiting.insert((self.at, id), cx.waker().clone());
+        if !sleepers.running {
+            thread::spawn(run_timer);
+            sleepers.running = true;
+        }
+        drop(sleepers);
+        self.id = Some(id);
+        CHANGED.notify_one();
+        Poll::Pending
+    }
+}
+
+impl Drop for ThreadSleep {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// A point in time which a task can wait for, from the given `Timer`, or `ThreadTimer`.
+pub(crate) struct Deadline {
+    sleep: Sleep,
+}
+
+impl Deadline {
+    pub(crate) fn after(timer: Option<&Arc<dyn Timer>>, duration: Duration) -> Deadline {
+        Deadline {
+            sleep: match timer {
+                Some(timer) => timer.sleep(duration),
+                None => ThreadTimer.sleep(duration),
+            },
+        }
+    }
+
+    pub(crate) fn poll(&mut self, cx: &mut Context) -> Poll<()> {
+        self.sleep.as_mut().poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use futures::future::join_all;
+    use futures::future::poll_fn;
+    use futures::task::Poll;
+
+    use super::ThreadSleep;
+    use super::ThreadTimer;
+    use super::Timer;
+    use super::SLEEPERS;
+
+    #[test]
+    fn shared_thread() {
+        let start = Instant::now();
+        let sleeps = (1..=20).map(|i| ThreadTimer.sleep(Duration::from_millis(i)));
+        async_std::task::block_on(join_all(sleeps));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn drop_cancels() {
+        let mut sleep = ThreadSleep {
+            at: Instant::now() + Duration::from_secs(3600),
+            id: None,
+        };
+        async_std::task::block_on(poll_fn(|cx| {
+            assert!(Pin::new(&mut sleep).poll(cx).is_pending());
+            Poll::Ready(())
+        }));
+        let key = (sleep.at, sleep.id.expect("registered"));
+        let waiting = || SLEEPERS.lock().unwrap().waiting.contains_key(&key);
+        assert!(waiting());
+        drop(sleep);
+        assert!(!waiting());
+    }
+}
@@ -0,0 +1,77 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Record every byte read from the inner reader, in order, for snapshot testing.
+    /// Reads are passed through unchanged.
+    pub struct Transcript<R> {
+        #[pin]
+        inner: R,
+        transcript: Vec<u8>,
+    }
+}
+
+impl<R> Transcript<R> {
+    pub fn new(inner: R) -> Self {
+        Transcript {
+            inner,
+            transcript: Vec::new(),
+        }
+    }
+
+    /// Everything which has been read so far.
+    pub fn transcript(&self) -> &[u8] {
+        &self.transcript
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Transcript<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        this.transcript.extend_from_slice(&buf[..found]);
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::ShortRead;
+    use crate::Transcript;
+
+    #[test]
+    fn records_consumed() {
+        task::block_on(async {
+            let mut r = Transcript::new(ShortRead::new(
+                io::Cursor::new(b"\x00\x03abcleftover"),
+                vec![1, 0, 3, 2, 4].into_iter(),
+            ));
+
+            let mut len = [0u8; 2];
+            r.read_exact(&mut len).await.unwrap();
+            let mut body = vec![0u8; usize::from(u16::from_be_bytes(len))];
+            r.read_exact(&mut body).await.unwrap();
+
+            assert_eq!(b"abc", body.as_slice());
+            assert_eq!(b"\x00\x03abc", r.transcript());
+        });
+    }
+}
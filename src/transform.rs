@@ -0,0 +1,160 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncBufRead;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+use slice_deque::SliceDeque;
+
+use crate::DequeReader;
+
+/// A streaming codec, such as a decompressor, for use with `Transform`.
+pub trait StreamTransform {
+    /// Transform some prefix of `input`, appending any produced data to `output`.
+    ///
+    /// Returns how many bytes of `input` were used. Returning `0`, without producing
+    /// any output, indicates that more input is required to make progress.
+    fn transform(&mut self, input: &[u8], output: &mut SliceDeque<u8>) -> io::Result<usize>;
+}
+
+pin_project! {
+    /// Pass the data from a reader through a `StreamTransform`, reading the transformed data.
+    ///
+    /// If the inner reader reaches the end of the stream while there is input that the
+    /// transform has not used, the read fails with `UnexpectedEof`.
+    pub struct Transform<R, T> {
+        #[pin]
+        inner: DequeReader<R>,
+        transform: T,
+        out: SliceDeque<u8>,
+    }
+}
+
+impl<R, T: StreamTransform> Transform<R, T> {
+    pub fn new(inner: R, transform: T) -> Self {
+        Transform {
+            inner: DequeReader::new(inner),
+            transform,
+            out: SliceDeque::new(),
+        }
+    }
+
+    pub fn get_transform(&self) -> &T {
+        &self.transform
+    }
+
+    /// Note that any buffered, or transformed but unread, data is lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: AsyncRead, T: StreamTransform> AsyncRead for Transform<R, T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut this = self.project();
+        loop {
+            if !this.out.is_empty() || buf.is_empty() {
+                let using = this.out.len().min(buf.len());
+                buf[..using].copy_from_slice(&this.out.as_slice()[..using]);
+                this.out.drain(..using);
+                return Poll::Ready(Ok(using));
+            }
+
+            if !this.inner.buffer().is_empty() {
+                let used = this.transform.transform(this.inner.buffer(), this.out)?;
+                this.inner.as_mut().consume(used);
+                if 0 != used || !this.out.is_empty() {
+                    continue;
+                }
+            }
+
+            if !ready!(this.inner.as_mut().poll_read_more(cx))? {
+                if this.inner.buffer().is_empty() {
+                    return Poll::Ready(Ok(0));
+                }
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "stream ended with input the transform could not use",
+                )));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use async_std::task;
+    use futures::io::AsyncReadExt as _;
+    use futures::io::Cursor;
+    use slice_deque::SliceDeque;
+
+    use crate::ShortRead;
+    use crate::StreamTransform;
+    use crate::Transform;
+
+    struct Identity;
+
+    impl StreamTransform for Identity {
+        fn transform(&mut self, input: &[u8], output: &mut SliceDeque<u8>) -> io::Result<usize> {
+            output.extend_from_slice(input);
+            Ok(input.len())
+        }
+    }
+
+    /// Expands `(count, byte)` pairs.
+    struct RunLength;
+
+    impl StreamTransform for RunLength {
+        fn transform(&mut self, input: &[u8], output: &mut SliceDeque<u8>) -> io::Result<usize> {
+            let pairs = input.len() / 2;
+            for pair in input[..pairs * 2].chunks(2) {
+                output.extend_from_slice(&vec![pair[1]; usize::from(pair[0])]);
+            }
+            Ok(pairs * 2)
+        }
+    }
+
+    #[test]
+    fn identity() {
+        task::block_on(async {
+            let mut t = Transform::new(
+                ShortRead::new(Cursor::new(b"hello world"), vec![2, 0, 3, 9].into_iter()),
+                Identity,
+            );
+            let mut out = Vec::new();
+            t.read_to_end(&mut out).await.unwrap();
+            assert_eq!(b"hello world", out.as_slice());
+        });
+    }
+
+    #[test]
+    fn run_length() {
+        task::block_on(async {
+            let mut t = Transform::new(
+                ShortRead::new(
+                    Cursor::new(b"\x03a\x00b\x02c"),
+                    vec![1, 2, 1, 2].into_iter(),
+                ),
+                RunLength,
+            );
+            let mut out = Vec::new();
+            t.read_to_end(&mut out).await.unwrap();
+            assert_eq!(b"aaacc", out.as_slice());
+
+            let mut t = Transform::new(Cursor::new(b"\x03a\x02"), RunLength);
+            let mut out = Vec::new();
+            let err = t.read_to_end(&mut out).await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert_eq!(b"aaa", out.as_slice());
+        });
+    }
+}
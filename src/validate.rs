@@ -0,0 +1,118 @@
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+use crate::label;
+
+pin_project! {
+    /// Check each chunk read from the inner reader with `check`, e.g. to reject bytes
+    /// outside of a permitted character set, as the data streams through.
+    ///
+    /// If `check` returns an error, the read fails with it, prefixed with the label, if any,
+    /// and the chunk is not returned.
+    /// `check` is not called at the end of the stream.
+    pub struct Validate<R, F> {
+        #[pin]
+        inner: R,
+        check: F,
+        label: Option<&'static str>,
+    }
+}
+
+impl<R, F: FnMut(&[u8]) -> io::Result<()>> Validate<R, F> {
+    pub fn new(inner: R, check: F) -> Self {
+        Validate {
+            inner,
+            check,
+            label: None,
+        }
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// generated by this reader, including those from `check`.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: fmt::Debug, F> fmt::Debug for Validate<R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validate")
+            .field("label", &self.label)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead, F: FnMut(&[u8]) -> io::Result<()>> AsyncRead for Validate<R, F> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        if found > 0 {
+            if let Err(e) = (this.check)(&buf[..found]) {
+                return Poll::Ready(Err(match this.label {
+                    Some(_) => label::error(*this.label, e.kind(), e),
+                    None => e,
+                }));
+            }
+        }
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+
+    use crate::DequeReader;
+    use crate::ShortRead;
+    use crate::Validate;
+
+    fn ascii(chunk: &[u8]) -> io::Result<()> {
+        if chunk.is_ascii() {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "non-ASCII data"))
+        }
+    }
+
+    #[test]
+    fn rejects() {
+        task::block_on(async {
+            let mut m = DequeReader::new(Validate::new(
+                ShortRead::new(io::Cursor::new(b"plain\xffdata"), vec![5, 5].into_iter()),
+                ascii,
+            ));
+            assert!(m.read_more().await.unwrap());
+            assert_eq!(b"plain", m.buffer());
+            let err = m.read_more().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert_eq!(b"plain", m.buffer());
+
+            let mut m =
+                DequeReader::new(Validate::new(io::Cursor::new(b"\xff"), ascii).with_label("name"));
+            let err = m.read_more().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert_eq!("name: non-ASCII data", err.to_string());
+
+            let mut m = DequeReader::new(Validate::new(io::Cursor::new(b"fine"), ascii));
+            assert_eq!(b"fine".to_vec(), m.read_to_vec().await.unwrap());
+        });
+    }
+}
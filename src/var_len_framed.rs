@@ -0,0 +1,228 @@
+use std::convert::TryInto;
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use futures::Stream;
+use pin_project_lite::pin_project;
+
+use crate::label;
+use crate::FrameOutcome;
+use crate::FrameState;
+use crate::StatefulFramed;
+
+/// The encoding of the length header of each frame read by `VarLenFramed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthField {
+    U8,
+    U16Be,
+    U16Le,
+    U32Be,
+    U32Le,
+}
+
+impl LengthField {
+    fn width(self) -> usize {
+        match self {
+            LengthField::U8 => 1,
+            LengthField::U16Be | LengthField::U16Le => 2,
+            LengthField::U32Be | LengthField::U32Le => 4,
+        }
+    }
+
+    fn decode(self, header: &[u8]) -> usize {
+        match self {
+            LengthField::U8 => usize::from(header[0]),
+            LengthField::U16Be => {
+                usize::from(u16::from_be_bytes(header.try_into().expect("width")))
+            }
+            LengthField::U16Le => {
+                usize::from(u16::from_le_bytes(header.try_into().expect("width")))
+            }
+            LengthField::U32Be => u32::from_be_bytes(header.try_into().expect("width")) as usize,
+            LengthField::U32Le => u32::from_le_bytes(header.try_into().expect("width")) as usize,
+        }
+    }
+}
+
+/// The framing for `VarLenFramed`: a length field, and the longest payload it may announce.
+#[derive(Debug)]
+struct Prefixed {
+    field: LengthField,
+    max_len: usize,
+    label: Option<&'static str>,
+}
+
+impl FrameState for Prefixed {
+    fn feed(&mut self, buf: &[u8]) -> FrameOutcome {
+        let width = self.field.width();
+        if buf.len() < width {
+            return FrameOutcome::NeedMore;
+        }
+        let payload = self.field.decode(&buf[..width]);
+        let len = match width.checked_add(payload) {
+            Some(len) if payload <= self.max_len => len,
+            _ => {
+                return FrameOutcome::Error(label::error(
+                    self.label,
+                    io::ErrorKind::InvalidData,
+                    "frame too long",
+                ))
+            }
+        };
+        if buf.len() < len {
+            return FrameOutcome::NeedMore;
+        }
+        FrameOutcome::Frame { len }
+    }
+}
+
+pin_project! {
+    /// A `Stream` of the payloads of length-prefixed frames, with a configurable length field.
+    ///
+    /// If the inner reader ends in the middle of a frame, the last item is an
+    /// `UnexpectedEof` error. A frame announcing a payload longer than the limit set by
+    /// `set_max_len` fails with `InvalidData`, before the payload is read.
+    pub struct VarLenFramed<R> {
+        #[pin]
+        inner: StatefulFramed<R, Prefixed>,
+    }
+}
+
+impl<R> VarLenFramed<R> {
+    pub fn new(inner: R, field: LengthField) -> Self {
+        VarLenFramed {
+            inner: StatefulFramed::new(
+                inner,
+                Prefixed {
+                    field,
+                    max_len: 16 * 1024 * 1024,
+                    label: None,
+                },
+            ),
+        }
+    }
+
+    /// The longest payload which a frame's header may announce. 16MiB by default.
+    pub fn set_max_len(&mut self, max: usize) {
+        self.inner.get_state_mut().max_len = max;
+    }
+
+    /// Attach a label, which is included in the `Debug` output, and in the errors
+    /// generated by this stream.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.inner = self.inner.with_label(label);
+        self.inner.get_state_mut().label = Some(label);
+        self
+    }
+
+    /// Note that any buffered data is lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for VarLenFramed<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.inner.get_state();
+        f.debug_struct("VarLenFramed")
+            .field("label", &state.label)
+            .field("field", &state.field)
+            .field("max_len", &state.max_len)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead> Stream for VarLenFramed<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let inner = self.project().inner;
+        let width = inner.get_state().field.width();
+        Poll::Ready(ready!(inner.poll_next(cx)).map(|frame| {
+            frame.map(|mut frame| {
+                frame.drain(..width);
+                frame
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use async_std::task;
+    use futures::io;
+    use futures::stream::TryStreamExt as _;
+
+    use crate::LengthField;
+    use crate::ShortRead;
+    use crate::VarLenFramed;
+
+    #[test]
+    fn u8_frames() {
+        task::block_on(async {
+            let mut input = b"\x02ab\x00\xff".to_vec();
+            input.extend_from_slice(&[7u8; 255]);
+            let frames = VarLenFramed::new(
+                ShortRead::new(io::Cursor::new(input), vec![1, 0, 3, 100, 200].into_iter()),
+                LengthField::U8,
+            );
+            let frames: Vec<Vec<u8>> = frames.try_collect().await.unwrap();
+            assert_eq!(vec![b"ab".to_vec(), Vec::new(), vec![7u8; 255]], frames);
+        });
+    }
+
+    #[test]
+    fn u16_frames() {
+        task::block_on(async {
+            let mut input = b"\x00\x03abc\xff\xff".to_vec();
+            input.extend_from_slice(&vec![9u8; 0xffff]);
+            let frames = VarLenFramed::new(
+                // the second length field is split across reads
+                ShortRead::new(
+                    io::Cursor::new(input),
+                    [6, 0, 1].iter().copied().chain(iter::repeat(4096)),
+                ),
+                LengthField::U16Be,
+            );
+            let frames: Vec<Vec<u8>> = frames.try_collect().await.unwrap();
+            assert_eq!(vec![b"abc".to_vec(), vec![9u8; 0xffff]], frames);
+
+            let frames = VarLenFramed::new(
+                io::Cursor::new(b"\x03\x00abc\x05\x00ab"),
+                LengthField::U16Le,
+            )
+            .with_label("records");
+            let err = frames.try_collect::<Vec<_>>().await.unwrap_err();
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+            assert_eq!("records: stream ended inside a frame", err.to_string());
+        });
+    }
+
+    #[test]
+    fn too_long() {
+        task::block_on(async {
+            // the payload is never read, or this would be an `UnexpectedEof`
+            let frames =
+                VarLenFramed::new(io::Cursor::new(b"\xff\xff\xff\xff"), LengthField::U32Be)
+                    .with_label("records");
+            let err = frames.try_collect::<Vec<_>>().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            assert_eq!("records: frame too long", err.to_string());
+
+            let mut frames =
+                VarLenFramed::new(io::Cursor::new(b"\x03abc\x04abcd"), LengthField::U8);
+            frames.set_max_len(3);
+            assert_eq!(b"abc", frames.try_next().await.unwrap().unwrap().as_slice());
+            let err = frames.try_next().await.unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        });
+    }
+}
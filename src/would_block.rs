@@ -0,0 +1,82 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// Fail reads with `WouldBlock`, as a non-blocking socket would, to test code which
+    /// should translate it into `Pending`.
+    ///
+    /// The `schedule` is consulted once per read: `true` fails the read with `WouldBlock`,
+    /// without touching the inner reader. Otherwise, or once the schedule runs out, reads
+    /// are passed through.
+    pub struct WouldBlockRead<R, I> {
+        #[pin]
+        inner: R,
+        schedule: I,
+    }
+}
+
+impl<R, I: Iterator<Item = bool>> WouldBlockRead<R, I> {
+    pub fn new(inner: R, schedule: I) -> Self {
+        WouldBlockRead { inner, schedule }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead, I: Iterator<Item = bool>> AsyncRead for WouldBlockRead<R, I> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if let Some(true) = this.schedule.next() {
+            return Poll::Ready(Err(io::ErrorKind::WouldBlock.into()));
+        }
+        this.inner.poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::WouldBlockRead;
+
+    #[test]
+    fn would_block() {
+        task::block_on(async {
+            let mut r = WouldBlockRead::new(
+                io::Cursor::new(b"hello"),
+                vec![true, false, true, true].into_iter(),
+            );
+            let mut buf = [0u8; 2];
+            let mut seen = Vec::new();
+            let mut out = Vec::new();
+            loop {
+                match r.read(&mut buf).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        seen.push(Some(n));
+                        out.extend_from_slice(&buf[..n]);
+                    }
+                    Err(e) => {
+                        assert_eq!(io::ErrorKind::WouldBlock, e.kind());
+                        seen.push(None);
+                    }
+                }
+            }
+            assert_eq!(vec![None, Some(2), None, None, Some(2), Some(1)], seen);
+            assert_eq!(b"hello", out.as_slice());
+        });
+    }
+}
@@ -0,0 +1,84 @@
+use std::io;
+use std::pin::Pin;
+
+use futures::ready;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::AsyncRead;
+use pin_project_lite::pin_project;
+
+pin_project! {
+    /// XOR the data read from the inner reader with a repeating key, a trivial stream
+    /// cipher, as used by some obfuscated protocols. Applying it twice restores the data.
+    ///
+    /// The key is applied from the start of the stream, however it is split into reads.
+    pub struct XorStream<R> {
+        #[pin]
+        inner: R,
+        key: Vec<u8>,
+        pos: usize,
+    }
+}
+
+impl<R> XorStream<R> {
+    /// # Panics
+    ///
+    /// Panics if `key` is empty.
+    pub fn new(inner: R, key: Vec<u8>) -> Self {
+        assert!(!key.is_empty(), "key must be non-empty");
+        XorStream { inner, key, pos: 0 }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for XorStream<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let found = ready!(this.inner.poll_read(cx, buf))?;
+        for b in &mut buf[..found] {
+            *b ^= this.key[*this.pos];
+            *this.pos = (*this.pos + 1) % this.key.len();
+        }
+        Poll::Ready(Ok(found))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use futures::io;
+    use futures::io::AsyncReadExt as _;
+
+    use crate::ShortRead;
+    use crate::XorStream;
+
+    #[test]
+    fn round_trip() {
+        task::block_on(async {
+            let input = b"attack at dawn, or maybe dusk";
+            let mut encrypted = Vec::new();
+            XorStream::new(io::Cursor::new(input), b"key".to_vec())
+                .read_to_end(&mut encrypted)
+                .await
+                .unwrap();
+            assert_eq!(b'a' ^ b'k', encrypted[0]);
+            assert_eq!(b't' ^ b'y', encrypted[2]);
+
+            // reads which don't line up with the key
+            let mut r = XorStream::new(
+                ShortRead::new(io::Cursor::new(encrypted), vec![2, 0, 5, 1, 64].into_iter()),
+                b"key".to_vec(),
+            );
+            let mut decrypted = Vec::new();
+            r.read_to_end(&mut decrypted).await.unwrap();
+            assert_eq!(&input[..], decrypted.as_slice());
+        });
+    }
+}